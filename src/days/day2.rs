@@ -1,16 +1,33 @@
-pub fn day2() {
+use std::collections::BTreeMap;
+
+// `--rounds` lists every round where the guide lost points
+pub fn day2(args: &[String]) {
+    let mut show_rounds = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--rounds" => show_rounds = true,
+            _ => println!("Error: Unknown argument '{}'", arg)
+        }
+    }
+
     let input = include_str!("input/day2.txt");
 
-    let rounds = input.split("\r\n")
-                                    .map( |v| 
-                                        v.split_whitespace().collect::<Vec<_>>()
-                                    )
-                                    .collect::<Vec<_>>();
+    let rounds = match parse(input) {
+        Ok(rounds) => rounds,
+        Err(errors) => {
+            println!("Day 2");
+            for e in errors {
+                println!("Error: {}", e);
+            }
+            return;
+        }
+    };
 
     let mut score_1 = 0;
     let mut score_2 = 0;
 
-    for round in rounds {
+    for round in &rounds {
         score_1 += compete_1(round.get(0).unwrap(), 
                         round.get(1).unwrap());
 
@@ -20,7 +37,160 @@ pub fn day2() {
 
     println!("Day 2");
     println!("Part 1: Score with original strategy = {}", score_1);
-    println!("Part 2: Score with real strategy = {}", score_2)
+    println!("Part 2: Score with real strategy = {}", score_2);
+
+    let analysis = Analysis::new(&rounds);
+
+    println!("Analysis: Best possible score = {}", analysis.best_score);
+
+    for (mapping, score) in &analysis.mapping_scores {
+        println!("Analysis: Score with X/Y/Z as {}/{}/{} = {}", 
+                 shape_name(mapping[0]), shape_name(mapping[1]), shape_name(mapping[2]), score);
+    }
+
+    println!("Analysis: Rounds that lost points = {} ({} points lost)",
+             analysis.losing_rounds().count(),
+             analysis.best_score - score_1);
+
+    for ((theirs, mine), (count, lost)) in analysis.losses_by_play() {
+        println!("Analysis: {} {} lost {} points over {} rounds (best play {})", 
+                 theirs, mine, lost, count, best_play(theirs).0);
+    }
+
+    if show_rounds {
+        for r in analysis.losing_rounds() {
+            println!("Analysis: Round {} {} {} scored {}, {} would score {} (lost {})",
+                     r.round, r.theirs, r.mine, r.score, r.best_play, r.best, r.lost());
+        }
+    }
+}
+
+const MY_SHAPES: [&str; 3] = ["X", "Y", "Z"];
+
+// Rounds of the guide as [theirs, mine], skipping blank lines
+fn parse(input: &str) -> Result<Vec<Vec<&str>>, Vec<String>> {
+    let mut rounds = Vec::new();
+    let mut errors = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let round: Vec<_> = line.split_whitespace().collect();
+
+        match round.as_slice() {
+            [] => {}
+            [theirs, mine] if ["A", "B", "C"].contains(theirs) && MY_SHAPES.contains(mine) => rounds.push(round),
+            _ => errors.push(format!("Line {}: Round must be A, B or C then X, Y or Z, not '{}'", i + 1, line))
+        }
+    }
+
+    if errors.is_empty() { Ok(rounds) } else { Err(errors) }
+}
+
+struct RoundBreakdown<'a> {
+    round: usize,
+    theirs: &'a str,
+    mine: &'a str,
+    score: i32,
+    best_play: &'static str,
+    best: i32
+}
+
+impl RoundBreakdown<'_> {
+    fn lost(&self) -> i32 {
+        self.best - self.score
+    }
+}
+
+// What-if analysis of the guide, reading the second column as a shape
+// (the part 1 interpretation).
+struct Analysis<'a> {
+    best_score: i32,
+    mapping_scores: Vec<([&'static str; 3], i32)>,
+    rounds: Vec<RoundBreakdown<'a>>
+}
+
+impl<'a> Analysis<'a> {
+    fn new(rounds: &[Vec<&'a str>]) -> Self {
+        let breakdown: Vec<_> = rounds.iter().enumerate().map(|(i, round)| {
+            let theirs = round[0];
+            let mine = round[1];
+            let (best_play, best) = best_play(theirs);
+
+            RoundBreakdown {
+                round: i + 1,
+                theirs,
+                mine,
+                score: compete_1(theirs, mine),
+                best_play,
+                best
+            }
+        }).collect();
+
+        let mapping_scores = mappings().into_iter().map(|mapping| {
+            let score = rounds.iter().map(|round| {
+                let shape = MY_SHAPES.iter().position(|s| *s == round[1])
+                                            .map_or("", |i| mapping[i]);
+                compete_1(round[0], shape)
+            }).sum();
+
+            (mapping, score)
+        }).collect();
+
+        Self {
+            best_score: breakdown.iter().map(|r| r.best).sum(),
+            mapping_scores,
+            rounds: breakdown
+        }
+    }
+
+    fn losing_rounds(&self) -> impl Iterator<Item = &RoundBreakdown<'a>> {
+        self.rounds.iter().filter(|r| r.lost() > 0)
+    }
+
+    // Totals (round count, points lost) for each distinct losing play
+    fn losses_by_play(&self) -> BTreeMap<(&'a str, &'a str), (usize, i32)> {
+        let mut totals = BTreeMap::new();
+
+        for r in self.losing_rounds() {
+            let entry = totals.entry((r.theirs, r.mine)).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += r.lost();
+        }
+
+        totals
+    }
+}
+
+fn best_play(theirs: &str) -> (&'static str, i32) {
+    MY_SHAPES.iter()
+             .map(|mine| (*mine, compete_1(theirs, mine)))
+             .max_by_key(|(_, score)| *score)
+             .unwrap()
+}
+
+fn shape_name(mine: &str) -> &'static str {
+    match mine {
+        "X" => "Rock",
+        "Y" => "Paper",
+        "Z" => "Scissors",
+        _ => "?"
+    }
+}
+
+// All 6 ways of reading X/Y/Z as the three shapes
+fn mappings() -> Vec<[&'static str; 3]> {
+    let mut out = Vec::new();
+
+    for a in MY_SHAPES {
+        for b in MY_SHAPES {
+            for c in MY_SHAPES {
+                if a != b && b != c && a != c {
+                    out.push([a, b, c]);
+                }
+            }
+        }
+    }
+
+    out
 }
 
 fn compete_1(theirs: &str, mine: &str) -> i32 {
@@ -1,14 +1,187 @@
-pub fn day3() {
+use std::fmt;
+
+mod bench;
+
+use bench::benchmark;
+
+// One bit per item type, with bit n set for the item of priority n
+type ItemMask = u64;
+
+const GROUP_SIZE: usize = 3;
+
+// `--group-size <n>` sets how many elves share a badge.
+//
+// `--benchmark <n>` times the bitmask solution against the original on n
+// generated groups of rucksacks
+pub fn day3(args: &[String]) {
     let input = include_str!("input/day3.txt");
 
+    let mut group_size = GROUP_SIZE;
+    let mut benchmark_groups = None;
+    let mut args = args.iter();
+
+    println!("Day 3");
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--group-size" => {
+                match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => group_size = n,
+                    _ => println!("Error: --group-size needs a number")
+                }
+            }
+            "--benchmark" => {
+                match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => benchmark_groups = Some(n),
+                    _ => println!("Error: --benchmark needs a number of groups")
+                }
+            }
+            _ => println!("Error: Unknown argument '{}'", arg)
+        }
+    }
+
+    let diagnostics = validate(input);
+
+    if !diagnostics.is_empty() {
+        for d in diagnostics {
+            println!("Error: {}", d);
+        }
+        return;
+    }
+
     let rucksacks: Vec<_> = input.split_whitespace().collect();
 
-    let dupes = rucksacks.iter().map(|r| find_dupe(r));
-    let prior: u32 = dupes.map(|c| get_priority(&c)).sum();
+    println!("Part 1: Sum of priorities = {}", sum_dupes(&rucksacks));
 
-    println!("Day 3");
-    println!("Part 1: Sum of priorities = {}", prior);
+    match find_badges(&rucksacks, group_size) {
+        Ok(badges) => {
+            println!("Part 2: Sum of priorities = {}", badges.iter().map(get_priority).sum::<u32>());
+            println!("Part 2: Badges = {}", badges.iter().collect::<String>());
+        }
+        Err(e) => {
+            println!("Part 2: Error: {}", e);
+        }
+    }
+
+    if let Some(groups) = benchmark_groups {
+        benchmark(groups);
+    }
+}
+
+enum Problem {
+    OddLength(usize),
+    NonLetter(char),
+    MultipleDupes(String),
+    NoDupe
+}
+
+struct Diagnostic {
+    line: usize,
+    problem: Problem
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: ", self.line)?;
 
+        match &self.problem {
+            Problem::OddLength(len) => write!(f, "Rucksack has odd length {}", len),
+            Problem::NonLetter(c) => write!(f, "Rucksack contains non-letter {:?}", c),
+            Problem::MultipleDupes(items) => write!(f, "Compartments share multiple items: {}", items),
+            Problem::NoDupe => write!(f, "Compartments share no items")
+        }
+    }
+}
+
+// Checks every rucksack is well formed before solving
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+
+    for (i, rucksack) in input.lines().enumerate() {
+        let rucksack = rucksack.trim();
+        let mut problems = Vec::new();
+
+        if rucksack.is_empty() {
+            continue;
+        }
+
+        if !rucksack.len().is_multiple_of(2) {
+            problems.push(Problem::OddLength(rucksack.len()));
+        }
+
+        if let Some(c) = rucksack.chars().find(|c| !c.is_ascii_alphabetic()) {
+            problems.push(Problem::NonLetter(c));
+        }
+
+        // Compartments can only be compared once the line is well formed
+        if problems.is_empty() {
+            let (left, right) = rucksack.split_at(rucksack.len() / 2);
+            let common = item_mask(left) & item_mask(right);
+
+            match common.count_ones() {
+                0 => problems.push(Problem::NoDupe),
+                1 => {}
+                _ => problems.push(Problem::MultipleDupes(mask_to_items(common)))
+            }
+        }
+
+        out.extend(problems.into_iter().map(|problem| Diagnostic { line: i + 1, problem }));
+    }
+
+    out
+}
+
+fn sum_dupes(rucksacks: &[&str]) -> u32 {
+    rucksacks.iter().map(|r| find_dupe_mask(r)).sum()
+}
+
+// Finds the single item common to every rucksack in each group
+fn find_badges(rucksacks: &[&str], group_size: usize) -> Result<Vec<char>, String> {
+    if group_size == 0 {
+        return Err("Group size must be at least 1".to_string());
+    }
+
+    if !rucksacks.len().is_multiple_of(group_size) {
+        return Err(format!("Final group {} has {} of {} rucksacks",
+                           rucksacks.len() / group_size + 1,
+                           rucksacks.len() % group_size,
+                           group_size));
+    }
+
+    rucksacks.chunks(group_size).enumerate().map(|(i, group)| {
+        let common = group.iter().fold(ItemMask::MAX, |acc, r| acc & item_mask(r));
+
+        match common.count_ones() {
+            1 => Ok(priority_to_item(common.trailing_zeros())),
+            0 => Err(format!("Group {} has no common item", i + 1)),
+            _ => Err(format!("Group {} has multiple common items: {}", i + 1, mask_to_items(common)))
+        }
+    }).collect()
+}
+
+fn item_mask(items: &str) -> ItemMask {
+    items.chars().fold(0, |acc, c| acc | 1 << get_priority(&c))
+}
+
+fn mask_to_items(mask: ItemMask) -> String {
+    (1..=52).filter(|p| mask & (1 << p) != 0)
+            .map(priority_to_item)
+            .collect()
+}
+
+// Returns the priority of the item found in both compartments
+fn find_dupe_mask(rucksack: &str) -> u32 {
+    let (left, right) = rucksack.split_at(rucksack.len() / 2);
+
+    (item_mask(left) & item_mask(right)).trailing_zeros()
+}
+
+// Original Vec<char> based solution, kept for comparison
+fn sum_dupes_vec(rucksacks: &[&str]) -> u32 {
+    rucksacks.iter().map(|r| get_priority(&find_dupe(r))).sum()
+}
+
+fn sum_badges_vec(rucksacks: &[&str]) -> u32 {
     let mut i = 0;
     let mut sum = 0;
 
@@ -28,7 +201,7 @@ pub fn day3() {
         sum += get_priority(ints.get(0).unwrap());
     }
 
-    println!("Part 2: Sum of priorities = {}", sum)
+    sum
 }
 
 fn find_dupe(rucksack: &str) -> char {
@@ -68,4 +241,13 @@ fn get_priority(c : &char) -> u32 {
     else {
         p - 96
     }
-}
\ No newline at end of file
+}
+
+fn priority_to_item(p: u32) -> char {
+    if p > 26 {
+        char::from_u32(p + 38).unwrap()
+    }
+    else {
+        char::from_u32(p + 96).unwrap()
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::Instant;
+
+use super::{find_badges, get_priority, priority_to_item, sum_badges_vec, sum_dupes, sum_dupes_vec, GROUP_SIZE};
+
+pub fn benchmark(groups: usize) {
+    let input = generate_rucksacks(groups, 16);
+    let rucksacks: Vec<_> = input.iter().map(String::as_str).collect();
+
+    let start = Instant::now();
+    let vec_answer = (sum_dupes_vec(&rucksacks), sum_badges_vec(&rucksacks));
+    let vec_time = start.elapsed();
+
+    let start = Instant::now();
+    let badges = find_badges(&rucksacks, GROUP_SIZE).unwrap();
+    let mask_answer = (sum_dupes(&rucksacks), badges.iter().map(get_priority).sum());
+    let mask_time = start.elapsed();
+
+    if vec_answer != mask_answer {
+        println!("Benchmark: Error: Vec<char> gave {:?} but bitmask gave {:?}", vec_answer, mask_answer);
+        return;
+    }
+
+    println!("Benchmark: {} rucksacks, Vec<char> = {:?}, bitmask = {:?}", rucksacks.len(), vec_time, mask_time);
+}
+
+// Generates groups of three rucksacks, each with exactly one item shared by
+// its compartments and exactly one badge shared by the group. Each rucksack
+// in a group draws its other items from a different third of the priorities
+// so nothing else is common to all three.
+fn generate_rucksacks(groups: usize, compartment_len: usize) -> Vec<String> {
+    let mut rng = XorShift(0x2022_0003);
+    let mut out = Vec::new();
+
+    for _ in 0..groups {
+        let badge = rng.below(52) + 1;
+
+        for k in 0..3 {
+            let mut pool: Vec<_> = (1..=52).filter(|p| p % 3 == k && *p != badge).collect();
+            let dupe = pool.swap_remove(rng.below(pool.len() as u32) as usize);
+
+            let mut left = vec![dupe, badge];
+            let mut right = vec![dupe];
+
+            for p in pool {
+                if rng.below(2) == 0 {
+                    left.push(p);
+                } else {
+                    right.push(p);
+                }
+            }
+
+            let mut fill = |items: &[u32]| -> String {
+                (0..compartment_len).map(|i| {
+                    // Make sure every chosen item appears at least once
+                    let p = items.get(i).copied().unwrap_or_else(|| items[rng.below(items.len() as u32) as usize]);
+                    priority_to_item(p)
+                }).collect()
+            };
+
+            let mut rucksack = fill(&left[..left.len().min(compartment_len)]);
+            rucksack.push_str(&fill(&right[..right.len().min(compartment_len)]));
+
+            out.push(rucksack);
+        }
+    }
+
+    out
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: u32) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+
+        (self.0 % n as u64) as u32
+    }
+}
@@ -1,47 +1,312 @@
+use std::{cmp::{max, min}, str::FromStr};
+
+// Inclusive range of section IDs assigned to an elf
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Assignment {
+    start: u32,
+    end: u32
+}
+
+impl Assignment {
+    fn new(start: u32, end: u32) -> Self {
+        Self { start, end }
+    }
+
+    fn contains(&self, other: &Assignment) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    fn overlaps(&self, other: &Assignment) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    fn intersection(&self, other: &Assignment) -> Option<Assignment> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        Some(Assignment::new(max(self.start, other.start), min(self.end, other.end)))
+    }
+
+    // The union is only a single range if the two overlap or are adjacent
+    fn union(&self, other: &Assignment) -> Option<Assignment> {
+        if self.start > other.end.saturating_add(1) || other.start > self.end.saturating_add(1) {
+            return None;
+        }
+
+        Some(Assignment::new(min(self.start, other.start), max(self.end, other.end)))
+    }
+
+    fn len(&self) -> u64 {
+        self.end as u64 - self.start as u64 + 1
+    }
+}
+
+impl FromStr for Assignment {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').ok_or("Assignment must be of the form a-b")?;
+
+        let start = start.parse::<u32>().map_err(|_| "Failed to parse assignment start")?;
+        let end = end.parse::<u32>().map_err(|_| "Failed to parse assignment end")?;
+
+        if start > end {
+            return Err("Assignment start is after its end");
+        }
+
+        Ok(Assignment::new(start, end))
+    }
+}
+
+// Elves sharing a line of the input, usually a pair
+struct Group {
+    members: Vec<Assignment>
+}
+
+impl Group {
+    // Some member's assignment contains everyone else's
+    fn one_contains_all(&self) -> bool {
+        self.members.iter().any(|a| self.members.iter().all(|b| a.contains(b)))
+    }
+
+    // At least two members share a section
+    fn any_overlap(&self) -> bool {
+        let mut sorted = self.members.clone();
+        sorted.sort_by_key(|a| a.start);
+
+        sorted.windows(2).try_fold(sorted[0].end, |furthest, w| {
+            if w[1].start <= furthest {
+                None
+            } else {
+                Some(max(furthest, w[1].end))
+            }
+        }).is_none()
+    }
+
+    // Sections shared by every member
+    fn common(&self) -> Option<Assignment> {
+        self.members.iter().skip(1).try_fold(self.members[0], |acc, a| acc.intersection(a))
+    }
+}
+
+impl FromStr for Group {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let members = s.split(',').map(Assignment::from_str).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Group { members })
+    }
+}
+
+// Number of elves covering each section, as runs of sections with the same depth
+struct Coverage {
+    segments: Vec<(Assignment, usize)>
+}
+
+impl Coverage {
+    fn new(assignments: &[Assignment]) -> Self {
+        // Sweep over +1 at each start and -1 after each end, which can be
+        // past u32::MAX
+        let mut events: Vec<(u64, i32)> = assignments.iter()
+                                                    .flat_map(|a| [(a.start as u64, 1), (a.end as u64 + 1, -1)])
+                                                    .collect();
+        events.sort();
+
+        let mut segments = Vec::new();
+        let mut depth = 0;
+
+        for (i, (section, change)) in events.iter().enumerate() {
+            depth += change;
+
+            if let Some((next, _)) = events.get(i + 1) {
+                if next > section {
+                    segments.push((Assignment::new(*section as u32, (next - 1) as u32), depth as usize));
+                }
+            }
+        }
+
+        Self { segments }
+    }
+
+    // Disjoint ranges of sections covered by at least one elf
+    fn merged(&self) -> Vec<Assignment> {
+        let mut out: Vec<Assignment> = Vec::new();
+
+        for (segment, _) in self.segments.iter().filter(|(_, d)| *d > 0) {
+            match out.last_mut().and_then(|last| last.union(segment).map(|u| (last, u))) {
+                Some((last, u)) => *last = u,
+                None => out.push(*segment)
+            }
+        }
+
+        out
+    }
+
+    // Sections from 1 up to the last assigned section that nobody covers
+    fn gaps(&self) -> Vec<Assignment> {
+        let mut out = Vec::new();
+
+        if let Some((first, _)) = self.segments.first() {
+            if first.start > 1 {
+                out.push(Assignment::new(1, first.start - 1));
+            }
+        }
+
+        out.extend(self.segments.iter().filter(|(_, d)| *d == 0).map(|(s, _)| *s));
+        out
+    }
+
+    fn max_depth(&self) -> usize {
+        self.segments.iter().map(|(_, d)| *d).max().unwrap_or(0)
+    }
+
+    fn deepest(&self) -> Vec<Assignment> {
+        let max = self.max_depth();
+        self.segments.iter().filter(|(_, d)| *d == max).map(|(s, _)| *s).collect()
+    }
+}
+
+// Indexes of the fewest assignments that still cover every section in
+// the camp, picking whichever reaches furthest at each uncovered section
+fn minimal_cover(assignments: &[Assignment]) -> Vec<usize> {
+    let mut order: Vec<_> = (0..assignments.len()).collect();
+    order.sort_by_key(|i| assignments[*i].start);
+
+    let mut chosen = Vec::new();
+    let mut next = 0;
+    let mut covered_to: Option<u64> = None;
+
+    while next < order.len() {
+        let from = match covered_to {
+            Some(end) if assignments[order[next]].start as u64 <= end + 1 => end + 1,
+            _ => assignments[order[next]].start as u64
+        };
+
+        let mut best: Option<usize> = None;
+
+        while next < order.len() && assignments[order[next]].start as u64 <= from {
+            let i = order[next];
+            if best.is_none_or(|b| assignments[i].end > assignments[b].end) {
+                best = Some(i);
+            }
+            next += 1;
+        }
+
+        if let Some(b) = best {
+            if assignments[b].end as u64 >= from {
+                chosen.push(b);
+                covered_to = Some(assignments[b].end as u64);
+            }
+        }
+    }
+
+    chosen
+}
+
+fn format_ranges(ranges: &[Assignment]) -> String {
+    if ranges.is_empty() {
+        return "none".to_string();
+    }
+
+    ranges.iter()
+          .map(|r| format!("{}-{}", r.start, r.end))
+          .collect::<Vec<_>>()
+          .join(", ")
+}
+
+fn camp_report(groups: &[Group]) {
+    let assignments: Vec<_> = groups.iter().flat_map(|g| g.members.iter().copied()).collect();
+    let owners: Vec<_> = groups.iter().enumerate().flat_map(|(i, g)| g.members.iter().map(move |_| i)).collect();
+    let coverage = Coverage::new(&assignments);
+
+    let merged = coverage.merged();
+    let gaps = coverage.gaps();
+
+    println!("Camp: Covered sections = {} ({} sections)", format_ranges(&merged), merged.iter().map(Assignment::len).sum::<u64>());
+    println!("Camp: Uncovered sections = {} ({} sections)", format_ranges(&gaps), gaps.iter().map(Assignment::len).sum::<u64>());
+    println!("Camp: Most elves on one section = {} at {}", coverage.max_depth(), format_ranges(&coverage.deepest()));
+
+    let cover = minimal_cover(&assignments);
+    let mut kept: Vec<_> = cover.iter().map(|i| owners[*i]).collect();
+    kept.sort();
+    kept.dedup();
+
+    let kept_names: Vec<_> = kept.iter().map(|i| (i + 1).to_string()).collect();
+    let spare: Vec<_> = (0..groups.len()).filter(|i| kept.binary_search(i).is_err()).map(|i| (i + 1).to_string()).collect();
+
+    println!("Camp: {} elves can cover every section, from groups {}", cover.len(), kept_names.join(", "));
+    println!("Camp: {} of {} groups could be reassigned entirely without losing coverage: {}",
+             spare.len(), groups.len(), if spare.is_empty() { "none".to_string() } else { spare.join(", ") });
+}
+
 pub fn day4() {
     let input = include_str!("input/day4.txt");
 
-    let lines = input.split_whitespace().collect::<Vec<_>>();
+    let groups: Result<Vec<_>, _> = input.split_whitespace().map(Group::from_str).collect();
 
-    let pairs = lines.iter().map(|l| l.split(",").collect::<Vec<_>>()).collect::<Vec<_>>();
+    println!("Day 4");
 
-    let contains = pairs.iter().filter(|p| does_contain(p)).collect::<Vec<_>>();
-    let overlaps = pairs.iter().filter(|p| does_overlap(p)).collect::<Vec<_>>();
+    match groups {
+        Ok(groups) => {
+            let contains = groups.iter().filter(|g| g.one_contains_all()).count();
+            let overlaps = groups.iter().filter(|g| g.any_overlap()).count();
+            let common = groups.iter().filter(|g| g.common().is_some()).count();
 
-    println!("Day 4");
-    println!("Part 1: Number of fully overlapped sets = {}", contains.len());
-    println!("Part 1: Number of partially overlapped sets = {}", overlaps.len());
-    
+            println!("Part 1: Number of fully overlapped sets = {}", contains);
+            println!("Part 2: Number of partially overlapped sets = {}", overlaps);
+            println!("Number of sets with a section shared by everyone = {}", common);
+
+            camp_report(&groups);
+        }
+        Err(e) => {
+            println!("Error: {}", e);
+        }
+    }
 }
 
-fn does_contain(p : &Vec<&str>) -> bool {
-    let p1 = p.get(0).unwrap().split("-").collect::<Vec<_>>();
-    let p2 = p.get(1).unwrap().split("-").collect::<Vec<_>>();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(pairs: &[(u32, u32)]) -> Vec<Assignment> {
+        pairs.iter().map(|(start, end)| Assignment::new(*start, *end)).collect()
+    }
 
-    let l1 = p1.get(0).unwrap().parse::<i32>().unwrap();
-    let r1 = p1.get(1).unwrap().parse::<i32>().unwrap();
-    let l2 = p2.get(0).unwrap().parse::<i32>().unwrap();
-    let r2 = p2.get(1).unwrap().parse::<i32>().unwrap();
+    #[test]
+    fn coverage_merges_and_finds_gaps() {
+        let coverage = Coverage::new(&ranges(&[(3, 5), (4, 8), (9, 10), (14, 15)]));
 
-    if (l1 <= l2 && r2 <= r1) || (l2 <= l1 && r1 <= r2) {
-        return true;
+        assert!(coverage.merged() == ranges(&[(3, 10), (14, 15)]));
+        assert!(coverage.gaps() == ranges(&[(1, 2), (11, 13)]));
+        assert_eq!(coverage.max_depth(), 2);
+        assert!(coverage.deepest() == ranges(&[(4, 5)]));
     }
 
-    false
-}
+    #[test]
+    fn coverage_reaches_the_last_section() {
+        let coverage = Coverage::new(&ranges(&[(u32::MAX - 2, u32::MAX), (u32::MAX, u32::MAX)]));
 
-fn does_overlap(p : &Vec<&str>) -> bool {
-    let p1 = p.get(0).unwrap().split("-").collect::<Vec<_>>();
-    let p2 = p.get(1).unwrap().split("-").collect::<Vec<_>>();
+        assert!(coverage.merged() == ranges(&[(u32::MAX - 2, u32::MAX)]));
+        assert!(coverage.deepest() == ranges(&[(u32::MAX, u32::MAX)]));
+        assert_eq!(Assignment::new(0, u32::MAX).len(), 1 << 32);
+    }
 
-    let l1 = p1.get(0).unwrap().parse::<i32>().unwrap();
-    let r1 = p1.get(1).unwrap().parse::<i32>().unwrap();
-    let l2 = p2.get(0).unwrap().parse::<i32>().unwrap();
-    let r2 = p2.get(1).unwrap().parse::<i32>().unwrap();
+    #[test]
+    fn minimal_cover_picks_the_furthest_reaching() {
+        let assignments = ranges(&[(1, 3), (2, 9), (1, 5), (6, 10), (10, 12), (20, 21)]);
+        let mut cover = minimal_cover(&assignments);
+        cover.sort();
 
-    if l1 > r2 || r1 < l2 {
-            return false;
+        assert_eq!(cover, vec![2, 3, 4, 5]);
     }
 
-    true
-}
\ No newline at end of file
+    #[test]
+    fn minimal_cover_stops_at_u32_max() {
+        let assignments = ranges(&[(u32::MAX - 5, u32::MAX), (u32::MAX - 1, u32::MAX), (u32::MAX, u32::MAX)]);
+
+        assert_eq!(minimal_cover(&assignments), vec![0]);
+        assert!(minimal_cover(&[]).is_empty());
+    }
+}
@@ -1,13 +1,22 @@
 use std::{
-    cmp::min, 
-    str::FromStr, 
-    string::ParseError
+    cmp::{max, min}, 
+    fmt,
+    fs,
+    str::FromStr
 };
 
-use regex::Regex;
+mod crane;
+mod history;
+mod planner;
+mod reverse;
+
+use crane::{crane_from_str, Crane, CrateMover9000, CrateMover9001};
+use history::History;
+use planner::plan;
+use reverse::{check_forward, reverse_solve, FinalState};
 
 struct Tower {
-    crates: Vec<char>,
+    crates: Vec<String>,
 }
 
 impl Tower {
@@ -71,6 +80,36 @@ impl Ship {
         to_tower.crates.append(&mut crates);
     }
     
+    // Checks a move only uses existing stacks and crates
+    fn check_move(&self, m: &Move) -> Result<(), String> {
+        for stack in [m.from, m.to] {
+            if stack == 0 || stack > self.towers.len() {
+                return Err(format!("Stack {} does not exist", stack));
+            }
+        }
+
+        let available = self.towers[m.from - 1].crates.len();
+
+        if m.count > available {
+            return Err(format!("Can't move {} crates from stack {} which only has {}", m.count, m.from, available));
+        }
+
+        Ok(())
+    }
+
+    fn has_long_labels(&self) -> bool {
+        self.towers.iter().flat_map(|t| &t.crates).any(|c| c.chars().count() != 1)
+    }
+
+    fn top_row(&self) -> String {
+        self.towers.iter().fold(String::new(), |mut acc, t| {
+            if let Some(top) = t.crates.last() {
+                acc.push_str(top);
+            }
+            acc
+        })
+    }
+
     fn get_or_create_tower_mut(&mut self, index : usize) -> &mut Tower {
         if index >= self.towers.len() {
             self.towers.resize(index + 1, Tower::new());
@@ -80,6 +119,13 @@ impl Ship {
     }
 }
 
+impl PartialEq for Ship {
+    fn eq(&self, other: &Self) -> bool {
+        self.towers.len() == other.towers.len() &&
+            self.towers.iter().zip(&other.towers).all(|(a, b)| a.crates == b.crates)
+    }
+}
+
 impl Clone for Ship {
     fn clone(&self) -> Self {
         let mut new_ship = Ship::new();
@@ -91,19 +137,110 @@ impl Clone for Ship {
 
 }
 
+// Pads both sides of `s` to fill `width`, with any odd space on the right
+fn centre(s: &str, width: usize) -> String {
+    let left = (width - s.len()) / 2;
+    let right = width - s.len() - left;
+
+    format!("{}{}{}", " ".repeat(left), s, " ".repeat(right))
+}
+
+// Draws the ship in the same format as the puzzle input
+impl fmt::Display for Ship {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let widths: Vec<_> = self.towers.iter().enumerate().map(|(i, t)| {
+            let label = t.crates.iter().map(String::len).max().unwrap_or(1);
+            max(label + 2, (i + 1).to_string().len())
+        }).collect();
+
+        let height = self.towers.iter().map(|t| t.crates.len()).max().unwrap_or(0);
+
+        for level in (0..height).rev() {
+            let row: Vec<_> = self.towers.iter().zip(&widths).map(|(t, w)| {
+                match t.crates.get(level) {
+                    Some(c) => centre(&format!("[{}]", c), *w),
+                    None => " ".repeat(*w)
+                }
+            }).collect();
+
+            writeln!(f, "{}", row.join(" "))?;
+        }
+
+        let footer: Vec<_> = widths.iter()
+                                   .enumerate()
+                                   .map(|(i, w)| centre(&(i + 1).to_string(), *w))
+                                   .collect();
+
+        write!(f, "{}", footer.join(" "))
+    }
+}
+
+// Start and end (inclusive) of each run of digits in the footer line, along
+// with the number it spells
+fn footer_columns(footer: &str) -> Vec<(usize, usize, &str)> {
+    let mut columns = Vec::new();
+    let mut start = None;
+
+    for (i, c) in footer.char_indices().chain([(footer.len(), ' ')]) {
+        match (c.is_ascii_digit(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                columns.push((s, i - 1, &footer[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    columns
+}
+
 impl FromStr for Ship {
-    type Err = ParseError;
+    type Err = &'static str;
 
-    fn from_str(rows: &str) -> Result<Self, Self::Err> {
+    fn from_str(drawing: &str) -> Result<Self, Self::Err> {
         let mut ship = Self::new();
 
-        for row in rows.split("\r\n") {
-            for (i, c) in row.chars().skip(1).step_by(4).enumerate() {
-                if c == ' ' {
-                    continue;
+        let mut rows: Vec<_> = drawing.lines().collect();
+        let footer = rows.pop().ok_or("Missing stack number footer")?;
+
+        let columns = footer_columns(footer);
+
+        if columns.is_empty() {
+            return Err("Missing stack number footer");
+        }
+
+        for (i, (_, _, label)) in columns.iter().enumerate() {
+            if label.parse::<usize>() != Ok(i + 1) {
+                return Err("Stack numbers must count up from 1");
+            }
+        }
+
+        ship.get_or_create_tower_mut(columns.len() - 1);
+
+        // Build from the bottom up so crates can simply be pushed
+        for row in rows.iter().rev() {
+            let mut chars = row.char_indices();
+
+            while let Some((open, c)) = chars.next() {
+                match c {
+                    ' ' => continue,
+                    '[' => {}
+                    _ => return Err("Unexpected character outside of a crate")
                 }
 
-                ship.get_or_create_tower_mut(i).crates.insert(0, c.clone());
+                let close = chars.find(|(_, c)| *c == ']')
+                                 .ok_or("Unclosed crate")?.0;
+
+                if close == open + 1 {
+                    return Err("Crate has an empty label");
+                }
+
+                let tower = columns.iter()
+                                   .position(|(start, end, _)| *start <= close && *end >= open)
+                                   .ok_or("Crate is not above a stack number")?;
+
+                ship.towers[tower].crates.push(row[open + 1..close].to_string());
             }
         }
 
@@ -111,56 +248,277 @@ impl FromStr for Ship {
     }
 }
 
-pub fn day5() {
+#[derive(Clone)]
+struct Move {
+    count: usize,
+    from: usize,
+    to: usize,
+    line: usize
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<_> = s.split_whitespace().collect();
+
+        let ["move", count, "from", from, "to", to] = tokens[..] else {
+            return Err("Move must be of the form 'move N from A to B'");
+        };
+
+        let num = |n: &str| n.parse::<usize>()
+                             .ok()
+                             .filter(|_| n.bytes().all(|b| b.is_ascii_digit()))
+                             .ok_or("Move numbers must be whole numbers that aren't too large");
+        let (count, from, to) = (num(count)?, num(from)?, num(to)?);
+
+        // Stacks are numbered from 1
+        if from == 0 || to == 0 {
+            return Err("Move stacks are numbered from 1");
+        }
+
+        Ok(Move {
+            count,
+            from,
+            to,
+            line: 0
+        })
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    step: bool,
+    strict: bool,
+    rewind: Option<usize>,
+    reverse: Option<Reverse>,
+    plan: Option<String>
+}
+
+// Where to start running the moves backwards from
+enum Reverse {
+    Own,
+    TopRow(String),
+    Drawing(String)
+}
+
+// Runs the puzzle with both standard cranes, plus any extra models given
+// with `--crane <name>`. `--step` prints the ship after every move,
+// `--strict` rejects impossible moves and `--rewind <n>` shows each ship
+// as it was after the first n moves.
+//
+// `--reverse` recovers the starting ship from each crane's own final ship,
+// `--reverse-top <row>` from just a top row and `--reverse-drawing <file>`
+// from a drawing of the final ship.
+//
+// `--plan <file>` instead finds moves that turn the starting ship into the
+// drawing in the file, using each `--crane` given or a CrateMover 9001.
+pub fn day5(args: &[String]) {
     let input = include_str!("input/day5.txt");
 
-    let mut ship_1;
-    let mut ship_2;
+    println!("Day 5");
+
+    let (ship, moves) = match parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("Error: {}", e);
+            return;
+        }
+    };
+
+    let mut options = Options::default();
+    let mut cranes = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--step" => options.step = true,
+            "--strict" => options.strict = true,
+            "--rewind" => {
+                match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) => options.rewind = Some(n),
+                    _ => println!("Error: --rewind needs a step number")
+                }
+            }
+            "--reverse" => options.reverse = Some(Reverse::Own),
+            "--reverse-top" => {
+                match args.next() {
+                    Some(top) => options.reverse = Some(Reverse::TopRow(top.clone())),
+                    None => println!("Error: --reverse-top needs a top row")
+                }
+            }
+            "--reverse-drawing" => {
+                match args.next() {
+                    Some(path) => options.reverse = Some(Reverse::Drawing(path.clone())),
+                    None => println!("Error: --reverse-drawing needs a file")
+                }
+            }
+            "--plan" => {
+                match args.next() {
+                    Some(path) => options.plan = Some(path.clone()),
+                    None => println!("Error: --plan needs a file")
+                }
+            }
+            "--crane" => {
+                match args.next().ok_or("Missing crane name".to_string()).and_then(|c| crane_from_str(c)) {
+                    Ok(crane) => cranes.push(crane),
+                    Err(e) => println!("Error: {}", e)
+                }
+            }
+            _ => println!("Error: Unknown argument '{}'", arg)
+        }
+    }
+
+    if let Some(path) = &options.plan {
+        if cranes.is_empty() {
+            cranes.push(Box::new(CrateMover9001));
+        }
+
+        let target = fs::read_to_string(path).map_err(|e| e.to_string())
+                                             .and_then(|s| Ship::from_str(&s).map_err(str::to_string));
+
+        match target {
+            Ok(target) => {
+                for crane in cranes {
+                    println!("{}:", crane.name());
+
+                    match plan(&ship, &target, crane.as_ref()) {
+                        Ok(moves) => moves.iter().for_each(|m| println!("{}", m)),
+                        Err(e) => println!("Error: {}", e)
+                    }
+                }
+            }
+            Err(e) => println!("Error: {}", e)
+        }
 
-    if let Some(tower_input) = input.split("\r\n\r\n").nth(0) {
-        ship_1 = Ship::from_str(tower_input).unwrap();
-        ship_2 = Ship::from_str(tower_input).unwrap();
-    } else {
         return;
     }
 
-    parse(input).iter().for_each(|(count, from, to)| {
-        ship_1.move_crate_serial(&count, &from, &to);
-        ship_2.move_crate(&count, &from, &to);
-    });
+    let mut all_cranes: Vec<(String, Box<dyn Crane>)> = vec![
+        ("Part 1".to_string(), Box::new(CrateMover9000)),
+        ("Part 2".to_string(), Box::new(CrateMover9001))
+    ];
+    all_cranes.extend(cranes.into_iter().map(|c| (c.name(), c)));
+
+    for (label, mut crane) in all_cranes {
+        let end = match run(&ship, &moves, crane.as_mut(), &label, &options) {
+            Ok(end) => end,
+            Err(e) => {
+                println!("{}: Error: {}", label, e);
+                continue;
+            }
+        };
+
+        println!("{}: Top row = {}", label, end.top_row());
 
-    let answer_1 = ship_1.towers.iter().fold(String::new(), |mut acc, t| {
-        if let Some(top) = t.crates.last() {
-            acc.push(*top);
+        let final_state = match &options.reverse {
+            None => continue,
+            Some(Reverse::Own) => Ok(FinalState::Drawing(end)),
+            Some(Reverse::TopRow(_)) if ship.has_long_labels() => {
+                Err("A top row can't be matched to stacks when crates have labels longer than one letter".to_string())
+            }
+            Some(Reverse::TopRow(top)) => Ok(FinalState::TopRow(top.clone(), ship.towers.len())),
+            Some(Reverse::Drawing(path)) => {
+                fs::read_to_string(path).map_err(|e| e.to_string())
+                                        .and_then(|s| Ship::from_str(&s).map_err(str::to_string))
+                                        .map(FinalState::Drawing)
+            }
+        };
+
+        let start = final_state.and_then(|f| {
+            reverse_solve(&f, &moves, crane.as_mut()).map(|start| {
+                let matches = check_forward(&start, &f, &moves, crane.as_mut());
+                (start, matches)
+            })
+        });
+
+        match start {
+            Ok((start, matches)) => {
+                println!("{}: Reversed starting ship:\n{}", label, start);
+                println!("{}: Running forwards again reproduces the final state = {}", label, matches);
+            }
+            Err(e) => println!("{}: Error: {}", label, e)
         }
-        acc
-    });
+    }
+}
+
+fn run(ship: &Ship, moves: &[Move], crane: &mut dyn Crane, label: &str, options: &Options) -> Result<Ship, String> {
+    let mut history = History::new(ship.clone());
 
-    let answer_2 = ship_2.towers.iter().fold(String::new(), |mut acc, t| {
-        if let Some(top) = t.crates.last() {
-            acc.push(*top);
+    if options.step {
+        println!("{}:\n{}\n", label, history.ship);
+    }
+
+    for m in moves {
+        history.apply(crane, m, options.strict)?;
+
+        if options.step {
+            println!("{}\n{}\n", m, history.ship);
         }
-        acc
-    });
+    }
 
-    println!("Day 5");
-    println!("Part 1: Top row = {}", answer_1);
-    println!("Part 2: Top row = {}", answer_2)
+    let end = history.ship.clone();
+
+    if let Some(n) = options.rewind {
+        history.rewind_to(n)?;
+        println!("{} after {} moves:\n{}\n", label, history.position(), history.ship);
+    }
+
+    Ok(end)
 }
 
-fn parse(line: &str) -> Vec<(usize, usize, usize)> {
-    // move (\d) from (\d) to (\d)
-    let re = Regex::new(r"move (\d+) from (\d) to (\d)").unwrap();
+// Splits the input into the starting ship and its list of moves
+fn parse(input: &str) -> Result<(Ship, Vec<Move>), String> {
+    let lines: Vec<_> = input.lines().collect();
+
+    let blank = lines.iter()
+                     .position(|l| l.trim().is_empty())
+                     .ok_or("Missing blank line after the drawing")?;
+
+    let ship = Ship::from_str(&lines[..blank].join("\n"))?;
+
+    let mut moves = Vec::new();
+
+    for (i, line) in lines.iter().enumerate().skip(blank + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut m = Move::from_str(line).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        m.line = i + 1;
+        moves.push(m);
+    }
+
+    Ok((ship, moves))
+}
 
-    let mut instructions = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for line in re.captures_iter(line) {
-        let i1 = line.get(1).unwrap().as_str().parse::<usize>().unwrap();
-        let i2 = line.get(2).unwrap().as_str().parse::<usize>().unwrap();
-        let i3 = line.get(3).unwrap().as_str().parse::<usize>().unwrap();
+    #[test]
+    fn reads_moves() {
+        let m = Move::from_str("move 12 from 3 to 10").unwrap();
+        assert_eq!((m.count, m.from, m.to), (12, 3, 10));
 
-        instructions.push((i1, i2, i3));
+        let m = Move::from_str("  move 1  from 2 to 1\r").unwrap();
+        assert_eq!((m.count, m.from, m.to), (1, 2, 1));
     }
 
-    instructions
-}
\ No newline at end of file
+    #[test]
+    fn rejects_bad_moves() {
+        for line in ["", "move 1 from 2", "move 1 from 2 to 3 now", "take 1 from 2 to 3", "move one from 2 to 3",
+                     "move +1 from 2 to 3", "move -1 from 2 to 3", "move 1 from 2 to 99999999999999999999999"] {
+            assert!(Move::from_str(line).is_err(), "'{}' was read as a move", line);
+        }
+
+        assert_eq!(Move::from_str("move 1 from 0 to 3").err(), Some("Move stacks are numbered from 1"));
+        assert_eq!(Move::from_str("move 1 from 2 to 0").err(), Some("Move stacks are numbered from 1"));
+    }
+}
@@ -0,0 +1,148 @@
+use super::{Move, Ship};
+
+pub trait Crane {
+    fn name(&self) -> String;
+
+    fn apply(&mut self, ship: &mut Ship, m: &Move);
+
+    fn clone_box(&self) -> Box<dyn Crane>;
+
+    // Anything about the crane that changes how its next move behaves
+    fn state(&self) -> usize {
+        0
+    }
+
+    // Undoes a move that has already been made
+    fn apply_inverse(&mut self, _ship: &mut Ship, _m: &Move) -> Result<(), String> {
+        Err(format!("{} can't run moves in reverse", self.name()))
+    }
+}
+
+// Both standard cranes are undone by moving the same crates straight back
+fn check_inverse(ship: &Ship, m: &Move) -> Result<(), String> {
+    let available = ship.towers.get(m.to - 1).map_or(0, |t| t.crates.len());
+
+    if m.count > available {
+        return Err(format!("Can't move {} crates back from stack {} which only has {}", m.count, m.to, available));
+    }
+
+    Ok(())
+}
+
+// Moves crates one at a time, reversing their order
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn clone_box(&self) -> Box<dyn Crane> {
+        Box::new(CrateMover9000)
+    }
+
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn apply(&mut self, ship: &mut Ship, m: &Move) {
+        ship.move_crate_serial(&m.count, &m.from, &m.to);
+    }
+
+    fn apply_inverse(&mut self, ship: &mut Ship, m: &Move) -> Result<(), String> {
+        check_inverse(ship, m)?;
+        ship.move_crate_serial(&m.count, &m.to, &m.from);
+        Ok(())
+    }
+}
+
+// Moves all the crates at once, keeping their order
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn clone_box(&self) -> Box<dyn Crane> {
+        Box::new(CrateMover9001)
+    }
+
+    fn name(&self) -> String {
+        "CrateMover 9001".to_string()
+    }
+
+    fn apply(&mut self, ship: &mut Ship, m: &Move) {
+        ship.move_crate(&m.count, &m.from, &m.to);
+    }
+
+    fn apply_inverse(&mut self, ship: &mut Ship, m: &Move) -> Result<(), String> {
+        check_inverse(ship, m)?;
+        ship.move_crate(&m.count, &m.to, &m.from);
+        Ok(())
+    }
+}
+
+// A CrateMover 9001 that can lift at most `capacity` crates, so splits
+// larger moves into several lifts
+pub struct CapacityCrane {
+    capacity: usize
+}
+
+impl Crane for CapacityCrane {
+    fn clone_box(&self) -> Box<dyn Crane> {
+        Box::new(CapacityCrane { capacity: self.capacity })
+    }
+
+    fn name(&self) -> String {
+        format!("CrateMover 9001 (max {})", self.capacity)
+    }
+
+    fn apply(&mut self, ship: &mut Ship, m: &Move) {
+        let mut remaining = m.count;
+
+        while remaining > 0 {
+            let batch = remaining.min(self.capacity);
+            ship.move_crate(&batch, &m.from, &m.to);
+            remaining -= batch;
+        }
+    }
+}
+
+// Keeps the order of one batch, then reverses the next
+pub struct AlternatingCrane {
+    moves: usize
+}
+
+impl Crane for AlternatingCrane {
+    fn clone_box(&self) -> Box<dyn Crane> {
+        Box::new(AlternatingCrane { moves: self.moves })
+    }
+
+    fn state(&self) -> usize {
+        self.moves % 2
+    }
+
+    fn name(&self) -> String {
+        "Alternating crane".to_string()
+    }
+
+    fn apply(&mut self, ship: &mut Ship, m: &Move) {
+        if self.moves.is_multiple_of(2) {
+            ship.move_crate(&m.count, &m.from, &m.to);
+        } else {
+            ship.move_crate_serial(&m.count, &m.from, &m.to);
+        }
+
+        self.moves += 1;
+    }
+}
+
+// Crane names as given on the command line, e.g. `9000`, `9001`,
+// `capacity:3` or `alternating`
+pub fn crane_from_str(s: &str) -> Result<Box<dyn Crane>, String> {
+    match s.split_once(':') {
+        None if s == "9000" => Ok(Box::new(CrateMover9000)),
+        None if s == "9001" => Ok(Box::new(CrateMover9001)),
+        None if s == "alternating" => Ok(Box::new(AlternatingCrane { moves: 0 })),
+        Some(("capacity", n)) => {
+            match n.parse::<usize>() {
+                Ok(capacity) if capacity > 0 => Ok(Box::new(CapacityCrane { capacity })),
+                _ => Err(format!("Invalid crane capacity '{}'", n))
+            }
+        }
+        _ => Err(format!("Unknown crane '{}'", s))
+    }
+}
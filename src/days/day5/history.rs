@@ -0,0 +1,177 @@
+use super::{crane::Crane, Move, Ship, Tower};
+
+// The towers a move touched, before and after it was made
+struct Step {
+    m: Move,
+    tower_count: usize,
+    before: (Tower, Tower),
+    after: (Tower, Tower)
+}
+
+// A ship along with every move made to it, so it can be rewound and
+// replayed to any step
+pub struct History {
+    pub ship: Ship,
+    done: Vec<Step>,
+    undone: Vec<Step>
+}
+
+impl History {
+    pub fn new(ship: Ship) -> Self {
+        Self {
+            ship,
+            done: Vec::new(),
+            undone: Vec::new()
+        }
+    }
+
+    // Number of moves currently applied
+    pub fn position(&self) -> usize {
+        self.done.len()
+    }
+
+    // Applies a move with the given crane, discarding anything that was
+    // undone. In strict mode invalid moves are rejected rather than
+    // stopping early or creating new stacks.
+    pub fn apply(&mut self, crane: &mut dyn Crane, m: &Move, strict: bool) -> Result<(), String> {
+        if strict {
+            self.ship.check_move(m).map_err(|e| format!("Line {}: {}", m.line, e))?;
+        }
+
+        let tower_count = self.ship.towers.len();
+        let before = self.snapshot(m);
+
+        crane.apply(&mut self.ship, m);
+
+        let after = self.snapshot(m);
+
+        self.done.push(Step { m: m.clone(), tower_count, before, after });
+        self.undone.clear();
+
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> Option<&Move> {
+        let step = self.done.pop()?;
+
+        self.restore(&step.m, &step.before);
+        self.ship.towers.truncate(step.tower_count);
+
+        self.undone.push(step);
+        self.undone.last().map(|s| &s.m)
+    }
+
+    pub fn redo(&mut self) -> Option<&Move> {
+        let step = self.undone.pop()?;
+
+        self.restore(&step.m, &step.after);
+
+        self.done.push(step);
+        self.done.last().map(|s| &s.m)
+    }
+
+    // Undoes or redoes moves until `step` moves have been applied
+    pub fn rewind_to(&mut self, step: usize) -> Result<(), String> {
+        if step > self.done.len() + self.undone.len() {
+            return Err(format!("Can't rewind to step {} of {}", step, self.done.len() + self.undone.len()));
+        }
+
+        while self.done.len() > step {
+            self.undo();
+        }
+
+        while self.done.len() < step {
+            self.redo();
+        }
+
+        Ok(())
+    }
+
+    fn snapshot(&mut self, m: &Move) -> (Tower, Tower) {
+        (self.ship.get_or_create_tower_mut(m.from - 1).clone(),
+         self.ship.get_or_create_tower_mut(m.to - 1).clone())
+    }
+
+    fn restore(&mut self, m: &Move, towers: &(Tower, Tower)) {
+        *self.ship.get_or_create_tower_mut(m.from - 1) = towers.0.clone();
+        *self.ship.get_or_create_tower_mut(m.to - 1) = towers.1.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use super::super::crane::CrateMover9000;
+
+    fn stacks(ship: &Ship) -> Vec<String> {
+        ship.towers.iter().map(|t| t.crates.concat()).collect()
+    }
+
+    fn example() -> (History, Vec<Move>) {
+        let ship = Ship::from_str("    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ").unwrap();
+        let moves = ["move 1 from 2 to 1", "move 3 from 1 to 3", "move 2 from 2 to 1"];
+        let moves = moves.iter().map(|m| Move::from_str(m).unwrap()).collect();
+
+        (History::new(ship), moves)
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let (mut history, moves) = example();
+
+        for m in &moves {
+            history.apply(&mut CrateMover9000, m, true).unwrap();
+        }
+        assert_eq!(stacks(&history.ship), ["CM", "", "PDNZ"]);
+
+        assert_eq!(history.undo().map(|m| m.from), Some(2));
+        assert_eq!(stacks(&history.ship), ["", "MC", "PDNZ"]);
+
+        assert_eq!(history.redo().map(|m| m.from), Some(2));
+        assert_eq!(stacks(&history.ship), ["CM", "", "PDNZ"]);
+        assert!(history.redo().is_none());
+
+        // A new move drops whatever was undone
+        history.undo();
+        history.apply(&mut CrateMover9000, &Move::from_str("move 1 from 3 to 2").unwrap(), true).unwrap();
+        assert!(history.redo().is_none());
+        assert_eq!(stacks(&history.ship), ["", "MCZ", "PDN"]);
+    }
+
+    #[test]
+    fn rewind_to_any_step() {
+        let (mut history, moves) = example();
+
+        for m in &moves {
+            history.apply(&mut CrateMover9000, m, true).unwrap();
+        }
+
+        history.rewind_to(0).unwrap();
+        assert_eq!(stacks(&history.ship), ["ZN", "MCD", "P"]);
+        assert!(history.undo().is_none());
+
+        history.rewind_to(2).unwrap();
+        assert_eq!(history.position(), 2);
+        assert_eq!(stacks(&history.ship), ["", "MC", "PDNZ"]);
+
+        assert!(history.rewind_to(4).is_err());
+        assert_eq!(history.position(), 2);
+    }
+
+    #[test]
+    fn undo_removes_stacks_a_move_created() {
+        let (mut history, _) = example();
+        let m = Move::from_str("move 1 from 3 to 5").unwrap();
+
+        assert!(history.apply(&mut CrateMover9000, &m, true).is_err());
+        assert_eq!(history.position(), 0);
+
+        history.apply(&mut CrateMover9000, &m, false).unwrap();
+        assert_eq!(stacks(&history.ship), ["ZN", "MCD", "", "", "P"]);
+
+        history.undo();
+        assert_eq!(stacks(&history.ship), ["ZN", "MCD", "P"]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet}
+};
+
+use super::{crane::Crane, Move, Ship, Tower};
+
+// Give up rather than searching forever on ships that are too big
+const MAX_SHIPS: usize = 200000;
+
+// Stacks of crates as indexes into the list of labels, plus the crane's
+// state. Much smaller to keep around than a whole ship.
+type State = (Vec<Vec<u16>>, usize);
+
+struct Labels {
+    names: Vec<String>,
+    ids: HashMap<String, u16>
+}
+
+impl Labels {
+    fn new(ship: &Ship) -> Self {
+        let mut names: Vec<_> = ship.towers.iter().flat_map(|t| t.crates.clone()).collect();
+        names.sort();
+        names.dedup();
+
+        let ids = names.iter().enumerate().map(|(i, n)| (n.clone(), i as u16)).collect();
+
+        Self { names, ids }
+    }
+
+    fn state(&self, ship: &Ship, crane: &dyn Crane) -> State {
+        (ship.towers.iter().map(|t| t.crates.iter().map(|c| self.ids[c]).collect()).collect(), crane.state())
+    }
+
+    fn ship(&self, state: &State) -> Ship {
+        let mut ship = Ship::new();
+
+        ship.towers = state.0.iter().map(|ids| {
+            let mut t = Tower::new();
+            t.crates = ids.iter().map(|i| self.names[*i as usize].clone()).collect();
+            t
+        }).collect();
+
+        ship
+    }
+}
+
+// How many crates are sitting above the part of each stack that already
+// matches the target. Every one has to move, but one move can carry
+// several, so this isn't a lower bound on moves and plans may not be the
+// shortest.
+fn misplaced(ship: &Ship, target: &Ship) -> usize {
+    ship.towers.iter().zip(&target.towers).map(|(t, goal)| {
+        let settled = t.crates.iter().zip(&goal.crates).take_while(|(a, b)| a == b).count();
+        t.crates.len() - settled
+    }).sum()
+}
+
+// Searches for a short list of moves that turns `start` into `target`
+// using the given crane, trying the ships with the fewest misplaced crates
+// first
+pub fn plan(start: &Ship, target: &Ship, crane: &dyn Crane) -> Result<Vec<Move>, String> {
+    if start.towers.len() != target.towers.len() {
+        return Err(format!("Start has {} stacks but the target has {}", start.towers.len(), target.towers.len()));
+    }
+
+    let mut have: Vec<_> = start.towers.iter().flat_map(|t| t.crates.clone()).collect();
+    let mut want: Vec<_> = target.towers.iter().flat_map(|t| t.crates.clone()).collect();
+    have.sort();
+    want.sort();
+
+    if have != want {
+        return Err("Start and target don't have the same crates".to_string());
+    }
+
+    if have.len() > u16::MAX as usize {
+        return Err("Too many crates to plan for".to_string());
+    }
+
+    let labels = Labels::new(start);
+    let goal = labels.state(target, crane).0;
+
+    // Every ship seen, and the move and node index that first reached each
+    let mut seen: HashSet<State> = HashSet::new();
+    let mut queue = BinaryHeap::new();
+    let mut nodes = vec![(labels.state(start, crane), crane.clone_box(), 0)];
+    let mut came_from: Vec<Option<(Move, usize)>> = vec![None];
+
+    seen.insert(nodes[0].0.clone());
+    queue.push(Reverse((misplaced(start, target), 0)));
+
+    while let Some(Reverse((_, node))) = queue.pop() {
+        let (key, node_crane, depth) = &nodes[node];
+
+        if key.0 == goal {
+            return Ok(unwind(&came_from, node));
+        }
+
+        let ship = labels.ship(key);
+
+        let mut next = Vec::new();
+
+        for from in 1..=ship.towers.len() {
+            for to in (1..=ship.towers.len()).filter(|to| *to != from) {
+                for count in 1..=ship.towers[from - 1].crates.len() {
+                    let m = Move { count, from, to, line: 0 };
+
+                    let mut new_ship = ship.clone();
+                    let mut new_crane = node_crane.clone_box();
+                    new_crane.apply(&mut new_ship, &m);
+
+                    let new_key = labels.state(&new_ship, new_crane.as_ref());
+
+                    if seen.contains(&new_key) {
+                        continue;
+                    }
+
+                    seen.insert(new_key.clone());
+
+                    if seen.len() > MAX_SHIPS {
+                        return Err(format!("No plan found within {} ships", MAX_SHIPS));
+                    }
+
+                    next.push((new_key, misplaced(&new_ship, target), new_crane, depth + 1, m));
+                }
+            }
+        }
+
+        for (new_key, left, new_crane, depth, m) in next {
+            queue.push(Reverse((depth + left, nodes.len())));
+            nodes.push((new_key, new_crane, depth));
+            came_from.push(Some((m, node)));
+        }
+    }
+
+    Err("Target can't be reached with this crane".to_string())
+}
+
+fn unwind(came_from: &[Option<(Move, usize)>], mut node: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+
+    while let Some((m, prev)) = &came_from[node] {
+        moves.push(m.clone());
+        node = *prev;
+    }
+
+    moves.reverse();
+    moves
+}
@@ -0,0 +1,69 @@
+use super::{crane::Crane, Move, Ship, Tower};
+
+// What is known about the ship once every move has been made
+pub enum FinalState {
+    Drawing(Ship),
+    // The letters on top of each stack, and how many stacks there are
+    TopRow(String, usize)
+}
+
+// Label given to crates that can't be recovered from a top row alone
+const UNKNOWN: &str = "?";
+
+// Runs the moves backwards from the final state to recover the starting
+// ship. From a top row only the crates that reach the top at the end can
+// be named, so any crate below them is left as `?`.
+pub fn reverse_solve(end: &FinalState, moves: &[Move], crane: &mut dyn Crane) -> Result<Ship, String> {
+    let mut ship = match end {
+        FinalState::Drawing(ship) => ship.clone(),
+        FinalState::TopRow(top, stacks) => ship_from_top_row(top, *stacks, moves)?
+    };
+
+    for m in moves.iter().rev() {
+        if let FinalState::TopRow(..) = end {
+            // These crates must have been hidden under the ones we know about
+            let tower = ship.get_or_create_tower_mut(m.to - 1);
+            let missing = m.count.saturating_sub(tower.crates.len());
+
+            tower.crates.splice(0..0, (0..missing).map(|_| UNKNOWN.to_string()));
+        }
+
+        crane.apply_inverse(&mut ship, m).map_err(|e| format!("Line {}: {}", m.line, e))?;
+    }
+
+    Ok(ship)
+}
+
+// Checks running the moves forwards from `start` gives the final state
+pub fn check_forward(start: &Ship, end: &FinalState, moves: &[Move], crane: &mut dyn Crane) -> bool {
+    let mut ship = start.clone();
+
+    for m in moves {
+        crane.apply(&mut ship, m);
+    }
+
+    match end {
+        FinalState::Drawing(end) => ship == *end,
+        FinalState::TopRow(top, _) => ship.top_row() == *top
+    }
+}
+
+// One single letter crate on each stack. A top row skips empty stacks, so
+// it can only be matched up with the stacks if it has a letter for every one.
+fn ship_from_top_row(top: &str, stacks: usize, moves: &[Move]) -> Result<Ship, String> {
+    let stacks = moves.iter().map(|m| m.from.max(m.to)).fold(stacks, usize::max);
+
+    if top.chars().count() != stacks {
+        return Err(format!("Top row has {} crates but the ship has {} stacks, so it can't say which stacks are empty", top.chars().count(), stacks));
+    }
+
+    let mut ship = Ship::new();
+
+    ship.towers = top.chars().map(|c| {
+        let mut t = Tower::new();
+        t.crates.push(c.to_string());
+        t
+    }).collect();
+
+    Ok(ship)
+}
@@ -1,52 +1,260 @@
-use std::{collections::{HashSet, VecDeque}};
+use std::{
+    fs::File,
+    io::{self, BufWriter, ErrorKind, Read, Write}
+};
 
-pub fn day6() {
-    let input = include_str!("input/day6.txt");
+const ALPHABET: usize = 256;
 
-    let packet_i = find_marker(4, input);
-    let message_i = find_marker(14, input);
+// Tracks the last `size` bytes of a stream in a ring buffer, along with how
+// many times each byte value appears in it
+struct MarkerScanner {
+    size: usize,
+    ring: Vec<u8>,
+    counts: [usize; 256],
+    distinct: usize,
+    seen: usize,
+    found: Option<usize>
+}
 
-    println!("Day 6");
-    println!("Part 1: Start-of-Packet = {}", packet_i.unwrap() + 1);
-    println!("Part 2: Start-of-Message = {}", message_i.unwrap() + 1);
+impl MarkerScanner {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            ring: vec![0; size],
+            counts: [0; 256],
+            distinct: 0,
+            seen: 0,
+            found: None
+        }
+    }
+
+    // Adds the next byte, returning true once the last `size` bytes have
+    // all been different
+    fn push(&mut self, b: u8) -> bool {
+        if self.found.is_some() {
+            return true;
+        }
+
+        let slot = self.seen % self.size;
+
+        if self.seen >= self.size {
+            let old = self.ring[slot] as usize;
+            self.counts[old] -= 1;
+
+            if self.counts[old] == 0 {
+                self.distinct -= 1;
+            }
+        }
+
+        self.ring[slot] = b;
+        self.counts[b as usize] += 1;
+
+        if self.counts[b as usize] == 1 {
+            self.distinct += 1;
+        }
+
+        self.seen += 1;
+
+        if self.distinct == self.size {
+            self.found = Some(self.seen);
+        }
+
+        self.found.is_some()
+    }
+}
+
+// Tracks the longest run of all-different bytes ending at the latest one,
+// from where each byte value was last seen
+struct DistinctRun {
+    last_seen: [Option<usize>; ALPHABET],
+    start: usize,
+    seen: usize
 }
 
-fn find_marker(count: usize, message: &str) -> Option<usize> {
-    let mut slice = VecDeque::new();
-    let mut hash = HashSet::new();
+impl DistinctRun {
+    fn new() -> Self {
+        Self {
+            last_seen: [None; ALPHABET],
+            start: 0,
+            seen: 0
+        }
+    }
+
+    // Adds the next byte, returning the length of the run ending with it.
+    // Every window up to that size ending here is a marker.
+    fn push(&mut self, b: u8) -> usize {
+        if let Some(last) = self.last_seen[b as usize] {
+            self.start = self.start.max(last + 1);
+        }
+
+        self.last_seen[b as usize] = Some(self.seen);
+        self.seen += 1;
 
-    message.chars().position(|c| {
-        slice.push_front(c);
-        let unique = hash.insert(c);
+        self.seen - self.start
+    }
 
-        if slice.len() > count {
-            let popped = slice.pop_back().unwrap();
+    fn alphabet_size(&self) -> usize {
+        self.last_seen.iter().filter(|l| l.is_some()).count()
+    }
+}
+
+// Calls `f` with each byte of the datastream until it returns false. The
+// datastream is a single line, so it ends at the first line terminator.
+fn for_each_byte<R: Read>(mut reader: R, mut f: impl FnMut(u8) -> bool) -> io::Result<()> {
+    let mut buf = [0; 8192];
 
-            if !slice.contains(&popped) {
-                hash.remove(&popped);
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        };
+
+        for b in &buf[..n] {
+            if *b == b'\n' || *b == b'\r' || !f(*b) {
+                return Ok(());
             }
         }
+    }
+}
+
+// Calls `on_marker` with every position (bytes read so far) where the last
+// `size` bytes were all different, returning how many there were
+fn all_markers<R: Read>(size: usize, reader: R, mut on_marker: impl FnMut(usize) -> io::Result<()>) -> io::Result<usize> {
+    let mut run = DistinctRun::new();
+    let mut found = 0;
+    let mut result = Ok(());
+
+    for_each_byte(reader, |b| {
+        if run.push(b) >= size {
+            found += 1;
+            result = on_marker(run.seen);
+        }
+        result.is_ok()
+    })?;
+
+    result.map(|_| found)
+}
+
+struct RunSummary {
+    // Position of the first marker of size n + 1, up to the alphabet size
+    first_markers: Vec<Option<usize>>,
+    // Position just after the longest all-different run, and its length
+    longest: (usize, usize)
+}
+
+// Finds the first marker of every size and the longest marker in one pass
+fn summarise_runs<R: Read>(reader: R) -> io::Result<RunSummary> {
+    let mut run = DistinctRun::new();
+    let mut first_markers = Vec::new();
+    let mut longest = (0, 0);
+
+    for_each_byte(reader, |b| {
+        let len = run.push(b);
+
+        while first_markers.len() < len {
+            first_markers.push(Some(run.seen));
+        }
+
+        if len > longest.1 {
+            longest = (run.seen, len);
+        }
+        true
+    })?;
+
+    first_markers.resize(first_markers.len().max(run.alphabet_size()), None);
+
+    Ok(RunSummary { first_markers, longest })
+}
+
+// Reads the stream once, returning how many bytes had been read when each
+// marker size was first found
+fn find_markers<R: Read>(sizes: &[usize], reader: R) -> io::Result<Vec<Option<usize>>> {
+    let mut scanners: Vec<_> = sizes.iter().map(|s| MarkerScanner::new(*s)).collect();
+
+    for_each_byte(reader, |b| {
+        let mut done = true;
+
+        for s in scanners.iter_mut() {
+            done &= s.push(b);
+        }
 
-        return hash.len() == count && unique;
-    })
+        !done
+    })?;
+
+    Ok(scanners.iter().map(|s| s.found).collect())
+}
+
+enum Mode {
+    Parts,
+    All(usize),
+    Summary
+}
+
+fn format_marker(marker: Option<usize>) -> String {
+    marker.map_or("None".to_string(), |i| i.to_string())
 }
 
-//Alternate method which is less efficient
-// fn find_marker(count: usize, message: &str) -> Option<usize> {
-//     let mut slice = VecDeque::new();
+// Scans the puzzle input, or `--stdin` / `--file <path>` to scan a stream
+// of any size. `--all <n>` lists every marker of size n and `--summary`
+// gives the first marker of every size and the longest marker.
+pub fn day6(args: &[String]) {
+    let input = include_str!("input/day6.txt");
+
+    let mut reader: Box<dyn Read> = Box::new(input.as_bytes());
+    let mut mode = Mode::Parts;
+    let mut args = args.iter();
+
+    println!("Day 6");
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stdin" => reader = Box::new(io::stdin().lock()),
+            "--file" => {
+                match args.next().map(File::open) {
+                    Some(Ok(f)) => reader = Box::new(f),
+                    Some(Err(e)) => {
+                        println!("Error: {}", e);
+                        return;
+                    }
+                    None => println!("Error: --file needs a path")
+                }
+            }
+            "--all" => {
+                match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => mode = Mode::All(n),
+                    _ => println!("Error: --all needs a marker size")
+                }
+            }
+            "--summary" => mode = Mode::Summary,
+            _ => println!("Error: Unknown argument '{}'", arg)
+        }
+    }
 
-//     message.chars().position(|c| {
-//      slice.push_front(c);
+    let result = match mode {
+        Mode::Parts => find_markers(&[4, 14], reader).map(|markers| {
+            println!("Part 1: Start-of-Packet = {}", format_marker(markers[0]));
+            println!("Part 2: Start-of-Message = {}", format_marker(markers[1]));
+        }),
+        Mode::All(n) => {
+            let mut out = BufWriter::new(io::stdout().lock());
 
-//         if slice.len() > count {
-//             slice.pop_back();
-//         }
+            all_markers(n, reader, |position| writeln!(out, "Marker at {}", position))
+                .and_then(|found| out.flush().map(|_| found))
+                .map(|found| println!("Markers of size {}: {} found", n, found))
+        }
+        Mode::Summary => summarise_runs(reader).map(|summary| {
+            for (i, marker) in summary.first_markers.iter().enumerate() {
+                println!("Size {}: First marker = {}", i + 1, format_marker(*marker));
+            }
 
-//         return slice.len() == count && unique_slice(&slice);
-//     })
-// }
+            let (end, len) = summary.longest;
+            println!("Longest marker = {} bytes, ending at {}", len, end);
+        })
+    };
 
-// fn unique_slice(slice: &VecDeque<char>) -> bool {
-//     let mut hash = HashSet::new();
-//     slice.iter().all(|c| hash.insert(c))
-// }
\ No newline at end of file
+    if let Err(e) = result {
+        println!("Error: {}", e);
+    }
+}
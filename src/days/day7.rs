@@ -1,142 +1,202 @@
-use std::{ collections::HashMap};
-
-struct Directory {
-    size: Option<usize>,
-    subs: Vec<String>,
-    files: Vec<usize>
+use std::{env, fs::{read_to_string, remove_dir_all}, path::PathBuf, process};
+
+mod cleanup;
+mod disk;
+mod filesystem;
+mod json;
+mod query;
+mod render;
+mod shell;
+
+use cleanup::{cleanup_plan, single_dir, space_to_free};
+use disk::{compare, materialise, transcript};
+use json::to_json;
+use query::run_query;
+use render::{du, parse_size, tree, RenderOptions};
+use shell::interpret;
+
+enum View {
+    Tree,
+    Du,
+    Materialise(PathBuf),
+    RoundTrip,
+    Json,
+    Query(Vec<String>)
 }
 
-impl Directory {
-    fn new() -> Self {
-        Self {
-            size: None,
-            subs: Vec::new(),
-            files: Vec::new()
+// `--capacity <size>` and `--needed <size>` set the disk for part 2.
+//
+// `--tree` and `--du` print the filesystem instead of the answers, limited
+// by `--depth <n>`, `--min <size>` and `--max <size>`. `-h` shows sizes
+// with units.
+//
+// `--input <file>` reads a different transcript, `--transcript <dir>` prints
+// the transcript for a real directory, `--materialise <dir>` writes the tree
+// out with sparse files and `--roundtrip` checks writing the tree out and
+// reading it back gives the same sizes.
+//
+// `--json` prints the tree as JSON, and `size <path>`, `find [filters]` or
+// `largest <n> [filters]` query it, see `query::run_query`.
+pub fn day7(args: &[String]) {
+    let mut input = include_str!("input/day7.txt").to_string();
+
+    let mut view = None;
+    let mut options = RenderOptions::default();
+    let mut capacity = 70000000;
+    let mut needed = 30000000;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tree" => view = Some(View::Tree),
+            "--du" => view = Some(View::Du),
+            "--roundtrip" => view = Some(View::RoundTrip),
+            "--json" => view = Some(View::Json),
+            "size" | "find" | "largest" => {
+                let mut query = vec![arg.clone()];
+                query.extend(args.by_ref().cloned());
+                view = Some(View::Query(query));
+            }
+            "--materialise" => {
+                match args.next() {
+                    Some(dir) => view = Some(View::Materialise(PathBuf::from(dir))),
+                    None => println!("Error: --materialise needs a directory")
+                }
+            }
+            "--transcript" => {
+                match args.next().map(|dir| transcript(&PathBuf::from(dir))) {
+                    Some(Ok(t)) => println!("{}", t),
+                    Some(Err(e)) => println!("Error: {}", e),
+                    None => println!("Error: --transcript needs a directory")
+                }
+                return;
+            }
+            "--input" => {
+                match args.next().map(read_to_string) {
+                    Some(Ok(text)) => input = text,
+                    Some(Err(e)) => {
+                        println!("Error: {}", e);
+                        return;
+                    }
+                    None => println!("Error: --input needs a file")
+                }
+            }
+            "-h" | "--human" => options.human = true,
+            "--depth" => {
+                match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) => options.depth = Some(n),
+                    _ => println!("Error: --depth needs a number")
+                }
+            }
+            "--capacity" | "--needed" => {
+                match args.next().ok_or(format!("{} needs a size", arg)).and_then(|s| parse_size(s)) {
+                    Ok(size) if arg == "--capacity" => capacity = size,
+                    Ok(size) => needed = size,
+                    Err(e) => println!("Error: {}", e)
+                }
+            }
+            "--min" | "--max" => {
+                match args.next().ok_or(format!("{} needs a size", arg)).and_then(|s| parse_size(s)) {
+                    Ok(size) if arg == "--min" => options.min = Some(size),
+                    Ok(size) => options.max = Some(size),
+                    Err(e) => println!("Error: {}", e)
+                }
+            }
+            _ => println!("Error: Unknown argument '{}'", arg)
         }
     }
 
-    fn add_sub(&mut self, name: String) {
-        self.subs.push(name);
-    }
-
-    fn get_size(& self, dirs: &HashMap<String,Directory>) -> usize {
-        if self.size.is_some() {
-            return self.size.unwrap();
+    let fs = match interpret(&input) {
+        Ok(fs) => fs,
+        Err(errors) => {
+            for e in errors {
+                println!("Error: {}", e);
+            }
+            return;
         }
+    };
 
-        let mut sum: usize = 0;
-
-        self.subs.iter().for_each(|s| {
-            sum += dirs.get(s).unwrap().get_size(dirs);
-        });
-        
-        sum + self.files.iter().sum::<usize>()
-    }
-}
-
-fn vtp(vec : &Vec<String>) -> String {
-    vec.iter().fold(String::new(), |mut acc, s| {
-        acc.push_str(&s); 
-        acc.push('/'); 
-        acc
-    })
-}
-
-pub fn day7() {
-    let input = include_str!("input/day7.txt");
-    let mut lines = input.split("\r\n");
-
-    let mut dirs: HashMap<String, Directory> = HashMap::new();
-
-    let l1 = lines.next();
-    assert!(l1 == Some("$ cd /"));
-
-    let mut c_path:Vec<String> = vec!["/".to_string()];
-
-    dirs.insert(vtp(&c_path), Directory::new());
-
-    lines.for_each(|line| {
-        let tokens = line.split_whitespace().collect::<Vec<_>>();
-
-        match tokens.get(0) {
-            Some(&"$") => {
-                let cmd = parse_cmd(tokens.get(1).unwrap(), tokens.get(2));
-                
-                match cmd {
-                    Some(Inst::CdBack) => {
-                        c_path.pop();
-                    }
-                    Some(Inst::CdTo(a)) => {
-                        let current = dirs.get_mut(&vtp(&c_path)).unwrap();
-                        c_path.push(a);
-                        current.add_sub(vtp(&c_path));
-                        drop(current);
-
-                        dirs.insert(vtp(&c_path), Directory::new());
-                    }
-                    _ => {
-                        return;
-                    } 
-                }
+    match view {
+        Some(View::Tree) => {
+            print!("{}", tree(&fs, &options));
+            return;
+        }
+        Some(View::Du) => {
+            print!("{}", du(&fs, &options));
+            return;
+        }
+        Some(View::Json) => {
+            print!("{}", to_json(&fs));
+            return;
+        }
+        Some(View::Query(query)) => {
+            match run_query(&fs, &query) {
+                Ok(out) => print!("{}", out),
+                Err(e) => println!("Error: {}", e)
+            }
+            return;
+        }
+        Some(View::Materialise(dir)) => {
+            match materialise(&fs, &dir) {
+                Ok(()) => println!("Wrote filesystem to {}", dir.display()),
+                Err(e) => println!("Error: {}", e)
+            }
+            return;
+        }
+        Some(View::RoundTrip) => {
+            let dir = env::temp_dir().join(format!("day7-{}", process::id()));
+
+            let result = materialise(&fs, &dir)
+                .and_then(|_| transcript(&dir))
+                .map_err(|e| e.to_string())
+                .and_then(|t| interpret(&t).map_err(|errors| errors.join("\n")))
+                .and_then(|copy| compare(&fs, &copy));
+
+            match result {
+                Ok(()) => println!("Round trip through {} gave the same filesystem", dir.display()),
+                Err(e) => println!("Round trip failed: {}", e)
             }
-            _ => {
-                if let Some(l) = tokens.get(0) {
-                    let current = dirs.get_mut(&vtp(&c_path)).unwrap();
-                    if let Ok(size) = l.parse::<usize>() {
-                        current.files.push(size);
-                        return;
-                    }
 
-                    // Ignore directories for now
-                }
+            if let Err(e) = remove_dir_all(&dir) {
+                println!("Error: Failed to clean up {}: {}", dir.display(), e);
             }
+            return;
         }
-    });
+        None => {}
+    }
 
     println!("Day 7");
-    let mut folder_sizes:Vec<(String, usize)> = dirs.iter().map(|(s,d)| (s.clone(), d.get_size(&dirs))).collect();
-    let sum: usize = folder_sizes.iter().map(|(_,s)| s).filter(|&s| *s <= 100000).sum();
+
+    let sum: usize = fs.dirs.iter().map(|d| d.size).filter(|s| *s <= 100000).sum();
     println!("Part 1: Sum of small directories = {}", sum);
 
-    let total_space = 70000000;
-    let total_used = folder_sizes.iter().find(|(s,_)| *s == "//".to_string()).unwrap().1;
+    let to_free = match space_to_free(&fs, capacity, needed) {
+        Ok(to_free) => to_free,
+        Err(e) => {
+            println!("Part 2: Error: {}", e);
+            return;
+        }
+    };
 
-    let total_unused = total_space - total_used;
-    let total_needed = 30000000 - total_unused;
+    if to_free == 0 {
+        println!("Part 2: Already have {} free", needed);
+        return;
+    }
 
-    folder_sizes.sort_by_key(|(_,si)| *si);
-    if let Some((f_name, f_size)) = folder_sizes.iter().find(|(_,s)| *s > total_needed) {
-        println!("Part 2: Should delete folder {} to free up {} of space", f_name, f_size);
+    if let Some(dir) = single_dir(&fs, to_free) {
+        println!("Part 2: Should delete folder {} to free up {} of space", fs.path(dir), fs.get(dir).size);
     }
     else {
         println!("Part 2: Failed");
-
     }
-}
 
-enum Inst {
-    CdBack,
-    CdTo(String),
-    List
-}
+    match cleanup_plan(&fs, to_free) {
+        Some(dirs) => {
+            let freed: usize = dirs.iter().map(|d| fs.get(*d).size).sum();
+            let paths: Vec<_> = dirs.iter().map(|d| fs.path(*d)).collect();
 
-fn parse_cmd(cmd: &str, param: Option<&&str>) -> Option<Inst> {
-    match cmd {
-        "cd" => {
-            if param.is_none() {
-                None
-            }
-            else {
-                if param.unwrap() == &".." {
-                    Some(Inst::CdBack)
-                }
-                else {
-                    Some(Inst::CdTo(param.unwrap().to_string()))
-                }
-            }
+            println!("Cleanup: Need to free {}, deleting [{}] frees {}", to_free, paths.join(", "), freed);
         }
-
-        "ls" => Some(Inst::List),
-        _ => None
+        None => println!("Cleanup: No set of folders frees {}", to_free)
     }
-} 
\ No newline at end of file
+}
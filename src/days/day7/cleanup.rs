@@ -0,0 +1,240 @@
+use super::filesystem::{FileSystem, ROOT};
+
+// How much has to be deleted so there is `needed` free out of `capacity`
+pub fn space_to_free(fs: &FileSystem, capacity: usize, needed: usize) -> Result<usize, String> {
+    let used = fs.get(ROOT).size;
+
+    if used > capacity {
+        return Err(format!("Filesystem uses {} which is more than the capacity {}", used, capacity));
+    }
+
+    Ok(needed.saturating_sub(capacity - used))
+}
+
+// The smallest single directory that frees enough space
+pub fn single_dir(fs: &FileSystem, to_free: usize) -> Option<usize> {
+    (0..fs.dirs.len()).filter(|d| fs.get(*d).size >= to_free)
+                      .min_by_key(|d| fs.get(*d).size)
+}
+
+// Most bits to spend on tracking sums, 128 MiB
+const MAX_BITS: usize = 1 << 30;
+
+// Sums that can be reached, one bit per sum
+struct Sums {
+    bits: Vec<u64>,
+    len: usize
+}
+
+impl Sums {
+    fn new(len: usize) -> Self {
+        Self { bits: vec![0; len.div_ceil(64)], len }
+    }
+
+    fn get(&self, i: usize) -> bool {
+        i < self.len && self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize) {
+        if i < self.len {
+            self.bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    // Adds every sum in `other` increased by `shift`
+    fn add_shifted(&mut self, other: &Sums, shift: usize) {
+        let words = shift / 64;
+        let offset = shift % 64;
+
+        for i in words..self.bits.len() {
+            let src = i - words;
+            let mut word = other.bits[src] << offset;
+
+            if offset > 0 && src > 0 {
+                word |= other.bits[src - 1] >> (64 - offset);
+            }
+
+            self.bits[i] |= word;
+        }
+
+        // Drop anything past the end of the last word
+        if !self.len.is_multiple_of(64) {
+            let last = self.bits.len() - 1;
+            self.bits[last] &= (1 << (self.len % 64)) - 1;
+        }
+    }
+}
+
+// Finds directories, none inside another, that free at least `to_free` while
+// deleting as little as possible. Walking the directories in order, each one
+// is either deleted, skipping everything inside it, or kept and stepped into,
+// and the sums of deleted sizes that reach each point are tracked as bits.
+//
+// Sums are counted in units of `scale` bytes, rounding each size down, to
+// keep the bits within MAX_BITS. With a scale of 1 the plan is the best
+// there is, above that it still frees enough but might not be the smallest.
+pub fn cleanup_plan(fs: &FileSystem, to_free: usize) -> Option<Vec<usize>> {
+    if to_free == 0 {
+        return Some(Vec::new());
+    }
+
+    // No plan can beat deleting one directory, so only sums below that matter
+    let single = single_dir(fs, to_free)?;
+    let single_size = fs.get(single).size;
+
+    // Directories in depth-first order, with where each one's contents end
+    let mut order = Vec::new();
+    let mut end = vec![0; fs.dirs.len()];
+    let mut stack = vec![(ROOT, false)];
+
+    while let Some((dir, done)) = stack.pop() {
+        if done {
+            end[dir] = order.len();
+            continue;
+        }
+
+        order.push(dir);
+        stack.push((dir, true));
+        stack.extend(fs.get(dir).subs.iter().rev().map(|s| (*s, false)));
+    }
+
+    let scale = (order.len() + 1).saturating_mul(single_size + 1).div_ceil(MAX_BITS).max(1);
+    let size = |dir: usize| fs.get(dir).size / scale;
+    let limit = single_size / scale + 1;
+
+    let mut reach: Vec<_> = (0..=order.len()).map(|_| Sums::new(limit)).collect();
+    reach[0].set(0);
+
+    for (i, dir) in order.iter().enumerate() {
+        let (before, after) = reach.split_at_mut(i + 1);
+        let here = &before[i];
+
+        // Keep it and look inside
+        after[0].add_shifted(here, 0);
+
+        // Delete it and skip its contents
+        after[end[*dir] - i - 1].add_shifted(here, size(*dir));
+    }
+
+    let Some(total) = (to_free.div_ceil(scale)..limit).find(|s| reach[order.len()].get(*s)) else {
+        return Some(vec![single]);
+    };
+
+    // Walk back through the choices that made the total
+    let mut chosen = Vec::new();
+    let mut at = order.len();
+    let mut sum = total;
+
+    while at > 0 {
+        let deleted = (0..at).find(|i| {
+            let size = size(order[*i]);
+            end[order[*i]] == at && size <= sum && reach[*i].get(sum - size)
+        });
+
+        match deleted {
+            Some(i) => {
+                chosen.push(order[i]);
+                sum -= size(order[i]);
+                at = i;
+            }
+            None => at -= 1
+        }
+    }
+
+    // Rounding can make the plan worse than deleting the one directory
+    if chosen.iter().map(|d| fs.get(*d).size).sum::<usize>() > single_size {
+        return Some(vec![single]);
+    }
+
+    chosen.reverse();
+    Some(chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Directories as (parent, size of the one file directly inside), added
+    // in order after the root
+    fn filesystem(dirs: &[(usize, usize)]) -> FileSystem {
+        let mut fs = FileSystem::new();
+
+        for (i, (parent, size)) in dirs.iter().enumerate() {
+            let dir = fs.add_dir(*parent, &format!("d{}", i + 1));
+            fs.add_file(dir, "f", *size);
+        }
+
+        fs.compute_sizes().unwrap();
+        fs
+    }
+
+    fn inside(fs: &FileSystem, dir: usize, other: usize) -> bool {
+        let mut current = Some(dir);
+
+        while let Some(d) = current {
+            if d == other {
+                return true;
+            }
+            current = fs.get(d).parent;
+        }
+
+        false
+    }
+
+    fn check_plan(fs: &FileSystem, to_free: usize, plan: &[usize]) {
+        let freed: usize = plan.iter().map(|d| fs.get(*d).size).sum();
+        let single = fs.get(single_dir(fs, to_free).unwrap()).size;
+
+        assert!(freed >= to_free, "Plan frees {} but {} is needed", freed, to_free);
+        assert!(freed <= single, "Plan frees {} but one directory frees {}", freed, single);
+
+        for a in plan {
+            assert!(plan.iter().all(|b| a == b || !inside(fs, *a, *b)), "Directory {} is deleted twice", a);
+        }
+    }
+
+    #[test]
+    fn several_directories_beat_one() {
+        let fs = filesystem(&[(ROOT, 60), (ROOT, 50), (ROOT, 100)]);
+
+        assert_eq!(single_dir(&fs, 110), Some(ROOT));
+        assert_eq!(cleanup_plan(&fs, 110), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn plan_never_deletes_inside_a_deleted_directory() {
+        // d1 holds 50 plus d2's 60, so d1 and d2 can't both go
+        let fs = filesystem(&[(ROOT, 50), (1, 60), (ROOT, 45)]);
+
+        assert_eq!(cleanup_plan(&fs, 105), Some(vec![2, 3]));
+        assert_eq!(cleanup_plan(&fs, 108), Some(vec![1]));
+    }
+
+    #[test]
+    fn nothing_or_too_much_to_free() {
+        let fs = filesystem(&[(ROOT, 10)]);
+
+        assert_eq!(cleanup_plan(&fs, 0), Some(Vec::new()));
+        assert_eq!(cleanup_plan(&fs, 11), None);
+    }
+
+    #[test]
+    fn large_sizes_are_scaled_down() {
+        let big = 1 << 40;
+        let fs = filesystem(&[(ROOT, big + 7), (ROOT, big / 2 + 3), (1, big / 2 - 1), (ROOT, big - 5)]);
+
+        for to_free in [big / 2, big + big / 2, 2 * big + 3] {
+            check_plan(&fs, to_free, &cleanup_plan(&fs, to_free).unwrap());
+        }
+    }
+
+    #[test]
+    fn rounding_falls_back_to_one_directory() {
+        // Scaled down, the directory that fits best rounds to less than
+        // what's needed, so no sum is found and it's picked on its own
+        let unit = 1 << 36;
+        let fs = filesystem(&[(ROOT, unit), (ROOT, unit + unit / 2), (ROOT, 2 * unit + 1)]);
+
+        assert_eq!(cleanup_plan(&fs, 2 * unit), Some(vec![3]));
+    }
+}
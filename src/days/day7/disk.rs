@@ -0,0 +1,164 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::Path
+};
+
+use super::filesystem::{FileSystem, ROOT};
+
+// Writes the tree out under `root`, using sparse files of the recorded sizes
+// so nothing is actually stored. Existing files are never overwritten.
+pub fn materialise(fs: &FileSystem, root: &Path) -> io::Result<()> {
+    for dir in 0..fs.dirs.len() {
+        let path = root.join(fs.path(dir).trim_start_matches('/'));
+        fs::create_dir_all(&path)?;
+
+        for f in &fs.get(dir).files {
+            let file = path.join(&f.name);
+
+            fs::File::create_new(&file)
+                .map_err(|e| io::Error::new(e.kind(), format!("Can't create {}: {}", file.display(), e)))?
+                .set_len(f.size as u64)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Walks a real directory and writes the `cd` / `ls` session that would
+// explore it, in the same format as the puzzle input. Symlinks are skipped.
+pub fn transcript(root: &Path) -> io::Result<String> {
+    let mut out = vec!["$ cd /".to_string()];
+    list_dir(root, &mut out)?;
+    Ok(out.join("\n"))
+}
+
+fn list_dir(path: &Path, out: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = fs::symlink_metadata(entry.path())?;
+
+        if !meta.is_dir() && !meta.is_file() {
+            continue;
+        }
+
+        // The transcript splits lines on whitespace, so names with any in
+        // them can't be read back
+        let name = match entry.file_name().into_string() {
+            Ok(name) if !name.is_empty() && !name.contains(char::is_whitespace) => name,
+            _ => {
+                let msg = format!("{} can't be written in a transcript", entry.path().display());
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+        };
+
+        entries.push((name, meta));
+    }
+
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    out.push("$ ls".to_string());
+
+    for (name, meta) in &entries {
+        if meta.is_dir() {
+            out.push(format!("dir {}", name));
+        } else if meta.is_file() {
+            out.push(format!("{} {}", meta.len(), name));
+        }
+    }
+
+    for (name, meta) in &entries {
+        if meta.is_dir() {
+            out.push(format!("$ cd {}", name));
+            list_dir(&path.join(name), out)?;
+            out.push("$ cd ..".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// Checks two trees have the same directories and files with the same sizes
+pub fn compare(a: &FileSystem, b: &FileSystem) -> Result<(), String> {
+    let by_path = |fs: &FileSystem| -> BTreeMap<_, _> {
+        fs.entries().into_iter().map(|e| (e.display_path(), e.size)).collect()
+    };
+    let (a_entries, b_entries) = (by_path(a), by_path(b));
+
+    for (path, size) in &a_entries {
+        match b_entries.get(path) {
+            None => return Err(format!("{} is missing", path)),
+            Some(other) if other != size => return Err(format!("{} is {} instead of {}", path, other, size)),
+            _ => {}
+        }
+    }
+
+    if let Some(extra) = b_entries.keys().find(|p| !a_entries.contains_key(*p)) {
+        return Err(format!("{} wasn't in the original", extra));
+    }
+
+    if a.get(ROOT).size != b.get(ROOT).size {
+        return Err("Total sizes differ".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process};
+
+    use super::*;
+    use super::super::shell::interpret;
+
+    // Empty directory under the system temp directory, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("day7-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const TRANSCRIPT: &str = "$ cd /\n$ ls\ndir a\n14848514 b.txt\ndir d\n$ cd a\n$ ls\n29116 f\n$ cd ..\n$ cd d\n$ ls\n4060174 j\n";
+
+    #[test]
+    fn materialised_tree_reads_back_the_same() {
+        let dir = TempDir::new("roundtrip");
+        let original = interpret(TRANSCRIPT).unwrap();
+
+        materialise(&original, &dir.0).unwrap();
+        let copy = interpret(&transcript(&dir.0).unwrap()).unwrap();
+
+        assert_eq!(compare(&original, &copy), Ok(()));
+    }
+
+    #[test]
+    fn materialise_never_overwrites() {
+        let dir = TempDir::new("overwrite");
+        fs::write(dir.0.join("b.txt"), "keep").unwrap();
+
+        assert!(materialise(&interpret(TRANSCRIPT).unwrap(), &dir.0).is_err());
+        assert_eq!(fs::read_to_string(dir.0.join("b.txt")).unwrap(), "keep");
+    }
+
+    #[test]
+    fn names_with_spaces_cant_be_transcribed() {
+        let dir = TempDir::new("spaces");
+        fs::write(dir.0.join("two words"), "").unwrap();
+
+        assert_eq!(transcript(&dir.0).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
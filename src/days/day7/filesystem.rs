@@ -0,0 +1,171 @@
+pub struct File {
+    pub name: String,
+    pub size: usize
+}
+
+pub struct Directory {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub subs: Vec<usize>,
+    pub files: Vec<File>,
+    // Whether an ls of this directory has been seen
+    pub listed: bool,
+    // Total size of everything below this directory, once computed
+    pub size: usize
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Dir,
+    File
+}
+
+pub struct Entry {
+    pub path: String,
+    pub name: String,
+    pub kind: Kind,
+    pub size: usize
+}
+
+impl Entry {
+    // Path with directories ending in `/`, so a file and a directory never
+    // look the same
+    pub fn display_path(&self) -> String {
+        if self.kind == Kind::Dir && self.path != "/" {
+            format!("{}/", self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+impl Directory {
+    fn new(name: &str, parent: Option<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            parent,
+            subs: Vec::new(),
+            files: Vec::new(),
+            listed: false,
+            size: 0
+        }
+    }
+}
+
+// Every directory is stored in one list, with the root first. Directories
+// are always added after their parent so each index is higher than its
+// parent's.
+pub struct FileSystem {
+    pub dirs: Vec<Directory>
+}
+
+pub const ROOT: usize = 0;
+
+impl FileSystem {
+    pub fn new() -> Self {
+        Self {
+            dirs: vec![Directory::new("", None)]
+        }
+    }
+
+    pub fn get(&self, dir: usize) -> &Directory {
+        &self.dirs[dir]
+    }
+
+    pub fn child(&self, dir: usize, name: &str) -> Option<usize> {
+        self.dirs[dir].subs.iter().copied().find(|s| self.dirs[*s].name == name)
+    }
+
+    pub fn file(&self, dir: usize, name: &str) -> Option<&File> {
+        self.dirs[dir].files.iter().find(|f| f.name == name)
+    }
+
+    // Returns the named subdirectory, creating it if it hasn't been seen
+    pub fn add_dir(&mut self, parent: usize, name: &str) -> usize {
+        if let Some(existing) = self.child(parent, name) {
+            return existing;
+        }
+
+        self.dirs.push(Directory::new(name, Some(parent)));
+
+        let index = self.dirs.len() - 1;
+        self.dirs[parent].subs.push(index);
+        index
+    }
+
+    pub fn add_file(&mut self, dir: usize, name: &str, size: usize) {
+        let files = &mut self.dirs[dir].files;
+
+        match files.iter_mut().find(|f| f.name == name) {
+            Some(f) => f.size = size,
+            None => files.push(File { name: name.to_string(), size })
+        }
+    }
+
+    // Fills in every directory's size in one pass from the deepest up
+    pub fn compute_sizes(&mut self) -> Result<(), &'static str> {
+        for d in self.dirs.iter_mut() {
+            d.size = d.files.iter().try_fold(0usize, |acc, f| acc.checked_add(f.size))
+                                   .ok_or("Directory sizes are too large to add up")?;
+        }
+
+        for i in (1..self.dirs.len()).rev() {
+            let size = self.dirs[i].size;
+            let parent = self.dirs[i].parent.unwrap();
+            self.dirs[parent].size = self.dirs[parent].size.checked_add(size)
+                                                          .ok_or("Directory sizes are too large to add up")?;
+        }
+
+        Ok(())
+    }
+
+    // Number of directories between this one and the root
+    pub fn depth(&self, dir: usize) -> usize {
+        let mut depth = 0;
+        let mut current = dir;
+
+        while let Some(parent) = self.dirs[current].parent {
+            depth += 1;
+            current = parent;
+        }
+
+        depth
+    }
+
+    pub fn path(&self, dir: usize) -> String {
+        let mut names = Vec::new();
+        let mut current = Some(dir);
+
+        while let Some(d) = current {
+            if d != ROOT {
+                names.push(self.dirs[d].name.as_str());
+            }
+            current = self.dirs[d].parent;
+        }
+
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Every directory and file in the tree
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut out = Vec::new();
+
+        for (i, d) in self.dirs.iter().enumerate() {
+            let path = self.path(i);
+
+            for f in &d.files {
+                out.push(Entry {
+                    path: if i == ROOT { format!("/{}", f.name) } else { format!("{}/{}", path, f.name) },
+                    name: f.name.clone(),
+                    kind: Kind::File,
+                    size: f.size
+                });
+            }
+
+            out.push(Entry { path, name: d.name.clone(), kind: Kind::Dir, size: d.size });
+        }
+
+        out
+    }
+}
@@ -0,0 +1,66 @@
+use super::filesystem::{FileSystem, ROOT};
+
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+
+    out
+}
+
+// The whole tree as nested JSON objects, e.g.
+// {"name": "/", "type": "dir", "size": 1234, "children": [...]}
+pub fn to_json(fs: &FileSystem) -> String {
+    let mut out = String::new();
+    write_dir(fs, ROOT, 0, &mut out);
+    out.push('\n');
+    out
+}
+
+fn write_dir(fs: &FileSystem, dir: usize, indent: usize, out: &mut String) {
+    let d = fs.get(dir);
+    let pad = "  ".repeat(indent + 1);
+    let name = if dir == ROOT { "/" } else { &d.name };
+
+    out.push_str("{\n");
+    out.push_str(&format!("{}\"name\": \"{}\",\n", pad, escape(name)));
+    out.push_str(&format!("{}\"type\": \"dir\",\n", pad));
+    out.push_str(&format!("{}\"size\": {},\n", pad, d.size));
+    out.push_str(&format!("{}\"children\": [", pad));
+
+    let count = d.subs.len() + d.files.len();
+
+    for (i, sub) in d.subs.iter().enumerate() {
+        out.push_str(&format!("\n{}  ", pad));
+        write_dir(fs, *sub, indent + 2, out);
+
+        if i + 1 < count {
+            out.push(',');
+        }
+    }
+
+    for (i, f) in d.files.iter().enumerate() {
+        out.push_str(&format!("\n{}  {{\"name\": \"{}\", \"type\": \"file\", \"size\": {}}}", pad, escape(&f.name), f.size));
+
+        if d.subs.len() + i + 1 < count {
+            out.push(',');
+        }
+    }
+
+    if count > 0 {
+        out.push_str(&format!("\n{}", pad));
+    }
+
+    out.push_str("]\n");
+    out.push_str(&format!("{}}}", "  ".repeat(indent)));
+}
@@ -0,0 +1,134 @@
+use super::{
+    filesystem::{Entry, FileSystem, Kind, ROOT},
+    render::parse_size
+};
+
+// Size of the directory or file at an absolute path
+pub fn size(fs: &FileSystem, path: &str) -> Result<usize, String> {
+    let mut dir = ROOT;
+    let names: Vec<_> = path.split('/').filter(|n| !n.is_empty()).collect();
+
+    for (i, name) in names.iter().enumerate() {
+        match fs.child(dir, name) {
+            Some(sub) => dir = sub,
+            None if i + 1 == names.len() => {
+                return fs.file(dir, name).map(|f| f.size).ok_or(format!("{} not found", path));
+            }
+            None => return Err(format!("{} not found", path))
+        }
+    }
+
+    Ok(fs.get(dir).size)
+}
+
+// Matches names against a pattern where `*` is any run of characters and
+// `?` is any one character
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<_> = pattern.chars().collect();
+    let n: Vec<_> = name.chars().collect();
+
+    // matches[j] is whether the pattern so far matches the first j characters
+    let mut matches = vec![false; n.len() + 1];
+    matches[0] = true;
+
+    for c in p {
+        let mut next = vec![false; n.len() + 1];
+
+        for j in 0..=n.len() {
+            next[j] = match c {
+                '*' => matches[j] || (j > 0 && next[j - 1]),
+                '?' => j > 0 && matches[j - 1],
+                c => j > 0 && matches[j - 1] && n[j - 1] == c
+            };
+        }
+
+        matches = next;
+    }
+
+    matches[n.len()]
+}
+
+#[derive(Default)]
+pub struct Filter {
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    pub name: Option<String>,
+    pub kind: Option<Kind>
+}
+
+impl Filter {
+    fn matches(&self, e: &Entry) -> bool {
+        self.min_size.is_none_or(|min| e.size >= min)
+            && self.max_size.is_none_or(|max| e.size <= max)
+            && self.name.as_ref().is_none_or(|pattern| glob_match(pattern, &e.name))
+            && self.kind.is_none_or(|kind| e.kind == kind)
+    }
+}
+
+pub fn find(fs: &FileSystem, filter: &Filter) -> Vec<Entry> {
+    let mut found: Vec<_> = fs.entries().into_iter().filter(|e| filter.matches(e)).collect();
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found
+}
+
+pub fn largest(fs: &FileSystem, count: usize, filter: &Filter) -> Vec<Entry> {
+    let mut found = find(fs, filter);
+    found.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    found.truncate(count);
+    found
+}
+
+fn parse_filter(args: &[String]) -> Result<Filter, String> {
+    let mut filter = Filter::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", arg))?;
+
+        match arg.as_str() {
+            "--min-size" => filter.min_size = Some(parse_size(value)?),
+            "--max-size" => filter.max_size = Some(parse_size(value)?),
+            "--name" => filter.name = Some(value.clone()),
+            "--type" => {
+                filter.kind = match value.as_str() {
+                    "d" => Some(Kind::Dir),
+                    "f" => Some(Kind::File),
+                    _ => return Err("--type must be d or f".to_string())
+                }
+            }
+            _ => return Err(format!("Unknown option '{}'", arg))
+        }
+    }
+
+    Ok(filter)
+}
+
+fn format_entries(entries: &[Entry]) -> String {
+    entries.iter()
+           .map(|e| format!("{}\t{}\n", e.size, e.display_path()))
+           .collect()
+}
+
+// Runs `size <path>`, `find [filters]` or `largest <n> [filters]`, where
+// the filters are `--min-size`, `--max-size`, `--name <glob>` and
+// `--type d|f`
+pub fn run_query(fs: &FileSystem, args: &[String]) -> Result<String, String> {
+    match args.first().map(String::as_str) {
+        Some("size") => {
+            let path = args.get(1).ok_or("size needs a path")?;
+            size(fs, path).map(|s| format!("{}\n", s))
+        }
+        Some("find") => {
+            let filter = parse_filter(&args[1..])?;
+            Ok(format_entries(&find(fs, &filter)))
+        }
+        Some("largest") => {
+            let count = args.get(1)
+                            .and_then(|n| n.parse::<usize>().ok())
+                            .ok_or("largest needs a count")?;
+            let filter = parse_filter(&args[2..])?;
+            Ok(format_entries(&largest(fs, count, &filter)))
+        }
+        _ => Err("Query must be size, find or largest".to_string())
+    }
+}
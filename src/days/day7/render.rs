@@ -0,0 +1,148 @@
+use super::filesystem::{FileSystem, ROOT};
+
+#[derive(Default)]
+pub struct RenderOptions {
+    // Deepest directory level to show, with the root at 0
+    pub depth: Option<usize>,
+    pub human: bool,
+    // Only show directories of at least `min` and at most `max`
+    pub min: Option<usize>,
+    pub max: Option<usize>
+}
+
+impl RenderOptions {
+    fn size(&self, size: usize) -> String {
+        if self.human {
+            human_size(size)
+        } else {
+            size.to_string()
+        }
+    }
+
+    fn in_range(&self, size: usize) -> bool {
+        self.min.is_none_or(|min| size >= min) && self.max.is_none_or(|max| size <= max)
+    }
+
+    fn filtered(&self) -> bool {
+        self.min.is_some() || self.max.is_some()
+    }
+}
+
+// Size in powers of 1024 with a unit suffix, like `du -h`
+pub fn human_size(size: usize) -> String {
+    let units = ["", "K", "M", "G", "T"];
+    let mut value = size as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        size.to_string()
+    } else if value < 10.0 {
+        format!("{:.1}{}", value, units[unit])
+    } else {
+        format!("{:.0}{}", value, units[unit])
+    }
+}
+
+// Parses a size with an optional K, M, G or T suffix in powers of 1024
+pub fn parse_size(s: &str) -> Result<usize, String> {
+    let units = ["K", "M", "G", "T"];
+    let upper = s.to_ascii_uppercase();
+
+    let (number, scale) = match units.iter().position(|u| upper.ends_with(u)) {
+        Some(i) => (&s[..s.len() - 1], 1024_usize.pow(i as u32 + 1)),
+        None => (s, 1)
+    };
+
+    number.parse::<usize>()
+          .ok()
+          .and_then(|n| n.checked_mul(scale))
+          .ok_or(format!("Invalid size '{}'", s))
+}
+
+// Indented view of the tree with the size of every directory and file.
+// When filtering by size, directories outside the range are only kept to
+// show the way to ones inside it, and files are left out.
+pub fn tree(fs: &FileSystem, options: &RenderOptions) -> String {
+    let mut out = format!("/ ({})\n", options.size(fs.get(ROOT).size));
+    tree_children(fs, ROOT, "", 1, options, &mut out);
+    out
+}
+
+fn tree_children(fs: &FileSystem, dir: usize, prefix: &str, depth: usize, options: &RenderOptions, out: &mut String) {
+    if options.depth.is_some_and(|max| depth > max) {
+        return;
+    }
+
+    let d = fs.get(dir);
+
+    let mut subs: Vec<_> = d.subs.iter().copied().filter(|s| shown(fs, *s, options)).collect();
+    subs.sort_by(|a, b| fs.get(*a).name.cmp(&fs.get(*b).name));
+
+    let mut files: Vec<_> = if options.filtered() { Vec::new() } else { d.files.iter().collect() };
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let count = subs.len() + files.len();
+
+    for (i, sub) in subs.iter().enumerate() {
+        let last = i + 1 == count;
+        let s = fs.get(*sub);
+
+        out.push_str(&format!("{}{}{}/ ({})\n", prefix, if last { "└── " } else { "├── " }, s.name, options.size(s.size)));
+
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        tree_children(fs, *sub, &child_prefix, depth + 1, options, out);
+    }
+
+    for (i, f) in files.iter().enumerate() {
+        let last = subs.len() + i + 1 == count;
+        out.push_str(&format!("{}{}{} ({})\n", prefix, if last { "└── " } else { "├── " }, f.name, options.size(f.size)));
+    }
+}
+
+// Whether a directory, or anything below it, is in the size range
+fn shown(fs: &FileSystem, dir: usize, options: &RenderOptions) -> bool {
+    options.in_range(fs.get(dir).size) || fs.get(dir).subs.iter().any(|s| shown(fs, *s, options))
+}
+
+// One line per directory, largest first, like `du | sort -rn`
+pub fn du(fs: &FileSystem, options: &RenderOptions) -> String {
+    let mut dirs: Vec<_> = (0..fs.dirs.len())
+        .filter(|d| options.depth.is_none_or(|max| fs.depth(*d) <= max))
+        .filter(|d| options.in_range(fs.get(*d).size))
+        .collect();
+
+    dirs.sort_by(|a, b| fs.get(*b).size.cmp(&fs.get(*a).size).then_with(|| fs.path(*a).cmp(&fs.path(*b))));
+
+    dirs.iter()
+        .map(|d| format!("{}\t{}\n", options.size(fs.get(*d).size), fs.path(*d)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_with_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("4k"), Ok(4096));
+        assert_eq!(parse_size("2M"), Ok(2 << 20));
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(70000000), "67M");
+    }
+
+    #[test]
+    fn sizes_that_dont_fit() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("T").is_err());
+        assert!(parse_size("-1K").is_err());
+        assert!(parse_size(&format!("{}K", usize::MAX)).is_err());
+        assert!(parse_size("16777216T").is_err());
+    }
+}
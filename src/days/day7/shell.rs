@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+
+use super::filesystem::{FileSystem, ROOT};
+
+enum Inst {
+    Cd(String),
+    List
+}
+
+fn parse_cmd(tokens: &[&str]) -> Result<Inst, String> {
+    match tokens {
+        ["cd", path] => Ok(Inst::Cd(path.to_string())),
+        ["cd", ..] => Err("cd needs exactly one path".to_string()),
+        ["ls"] => Ok(Inst::List),
+        ["ls", ..] => Err("ls doesn't take arguments".to_string()),
+        [cmd, ..] => Err(format!("Unknown command '{}'", cmd)),
+        [] => Err("Missing command".to_string())
+    }
+}
+
+// An `ls` whose output is still being read
+struct Listing {
+    dir: usize,
+    line: usize,
+    names: HashSet<String>
+}
+
+// Replays a shell transcript to rebuild the filesystem it explored
+struct Shell {
+    fs: FileSystem,
+    cwd: usize,
+    listing: Option<Listing>,
+    // Size of every file listed so far, which no directory can go over
+    total: usize,
+    errors: Vec<String>
+}
+
+impl Shell {
+    fn new() -> Self {
+        Self {
+            fs: FileSystem::new(),
+            cwd: ROOT,
+            listing: None,
+            total: 0,
+            errors: Vec::new()
+        }
+    }
+
+    fn error(&mut self, line: usize, msg: String) {
+        self.errors.push(format!("Line {}: {}", line, msg));
+    }
+
+    fn run_line(&mut self, line: usize, text: &str) {
+        let tokens: Vec<_> = text.split_whitespace().collect();
+
+        match tokens.first() {
+            None => {}
+            Some(&"$") => {
+                self.end_listing();
+
+                match parse_cmd(&tokens[1..]) {
+                    Ok(Inst::Cd(path)) => self.cd(line, &path),
+                    Ok(Inst::List) => {
+                        self.listing = Some(Listing { dir: self.cwd, line, names: HashSet::new() });
+                    }
+                    Err(e) => self.error(line, e)
+                }
+            }
+            Some(_) => self.list_entry(line, &tokens)
+        }
+    }
+
+    // Follows a path one directory at a time, from the root if it starts
+    // with `/`. Going up from the root stays at the root.
+    fn cd(&mut self, line: usize, path: &str) {
+        if path.starts_with('/') {
+            self.cwd = ROOT;
+        }
+
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            match name {
+                "." => {}
+                ".." => self.cwd = self.fs.get(self.cwd).parent.unwrap_or(ROOT),
+                _ => {
+                    if self.fs.get(self.cwd).listed && self.fs.child(self.cwd, name).is_none() {
+                        let msg = format!("cd into '{}' which wasn't listed in {}", name, self.fs.path(self.cwd));
+                        self.error(line, msg);
+                    }
+
+                    self.cwd = self.fs.add_dir(self.cwd, name);
+                }
+            }
+        }
+    }
+
+    fn list_entry(&mut self, line: usize, tokens: &[&str]) {
+        let dir = match &self.listing {
+            Some(l) => l.dir,
+            None => {
+                self.error(line, "Output without an ls".to_string());
+                return;
+            }
+        };
+
+        let (kind, name) = match tokens {
+            [kind, name] => (*kind, *name),
+            _ => {
+                self.error(line, format!("Can't read listing '{}'", tokens.join(" ")));
+                return;
+            }
+        };
+
+        // Names end up as paths on disk, so they must stay inside their directory
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            self.error(line, format!("'{}' isn't a valid name", name));
+            return;
+        }
+
+        if let Some(l) = &mut self.listing {
+            l.names.insert(name.to_string());
+        }
+
+        let existing_file = self.fs.file(dir, name).map(|f| f.size);
+        let existing_dir = self.fs.child(dir, name);
+
+        if kind == "dir" {
+            if existing_file.is_some() {
+                self.error(line, format!("'{}' was listed as a file before", name));
+                return;
+            }
+
+            self.fs.add_dir(dir, name);
+        }
+        else if let Ok(size) = kind.parse::<usize>() {
+            if existing_dir.is_some() {
+                self.error(line, format!("'{}' was listed as a directory before", name));
+                return;
+            }
+
+            if let Some(old) = existing_file.filter(|old| *old != size) {
+                self.error(line, format!("'{}' was listed with size {} before, now {}", name, old, size));
+                return;
+            }
+
+            if existing_file.is_none() {
+                match self.total.checked_add(size) {
+                    Some(total) => self.total = total,
+                    None => {
+                        self.error(line, format!("'{}' makes the files add up to more than {} bytes", name, usize::MAX));
+                        return;
+                    }
+                }
+            }
+
+            self.fs.add_file(dir, name, size);
+        }
+        else {
+            self.error(line, format!("Can't read listing '{}'", tokens.join(" ")));
+        }
+    }
+
+    // Once an ls is over, anything a previous ls of the same directory
+    // showed should have been shown again
+    fn end_listing(&mut self) {
+        let listing = match self.listing.take() {
+            Some(l) => l,
+            None => return
+        };
+
+        let dir = self.fs.get(listing.dir);
+
+        if dir.listed {
+            let mut missing: Vec<_> = dir.files.iter().map(|f| f.name.clone())
+                                          .chain(dir.subs.iter().map(|s| self.fs.get(*s).name.clone()))
+                                          .filter(|n| !listing.names.contains(n))
+                                          .collect();
+            missing.sort();
+
+            for name in missing {
+                let msg = format!("'{}' is missing from this listing of {}", name, self.fs.path(listing.dir));
+                self.error(listing.line, msg);
+            }
+        }
+
+        self.fs.dirs[listing.dir].listed = true;
+    }
+}
+
+// Rebuilds the filesystem from a transcript, or lists every problem with it
+pub fn interpret(transcript: &str) -> Result<FileSystem, Vec<String>> {
+    let mut shell = Shell::new();
+
+    for (i, text) in transcript.lines().enumerate() {
+        shell.run_line(i + 1, text);
+    }
+
+    shell.end_listing();
+
+    if !shell.errors.is_empty() {
+        return Err(shell.errors);
+    }
+
+    shell.fs.compute_sizes().map_err(|e| vec![e.to_string()])?;
+    Ok(shell.fs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(transcript: &str) -> Vec<String> {
+        interpret(transcript).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn rebuilds_sizes_from_a_transcript() {
+        let fs = interpret("$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a\n$ ls\n29116 f\n$ cd ..\n$ cd a\n").unwrap();
+
+        assert_eq!(fs.get(ROOT).size, 14877630);
+        assert_eq!(fs.get(fs.child(ROOT, "a").unwrap()).size, 29116);
+    }
+
+    #[test]
+    fn bad_commands() {
+        assert_eq!(errors("$ cd\n$ cd a b\n$ ls -l\n$ rm a\n$"), vec![
+            "Line 1: cd needs exactly one path",
+            "Line 2: cd needs exactly one path",
+            "Line 3: ls doesn't take arguments",
+            "Line 4: Unknown command 'rm'",
+            "Line 5: Missing command"
+        ]);
+    }
+
+    #[test]
+    fn bad_listings() {
+        assert_eq!(errors("dir a\n$ ls\n12 a b\nlots a\n"), vec![
+            "Line 1: Output without an ls",
+            "Line 3: Can't read listing '12 a b'",
+            "Line 4: Can't read listing 'lots a'"
+        ]);
+    }
+
+    #[test]
+    fn listings_that_disagree() {
+        let transcript = "$ ls\ndir a\n5 b\n$ ls\n5 a\ndir b\n$ ls\n6 b\n$ ls\n$ cd c";
+
+        assert_eq!(errors(transcript), vec![
+            "Line 5: 'a' was listed as a directory before",
+            "Line 6: 'b' was listed as a file before",
+            "Line 8: 'b' was listed with size 5 before, now 6",
+            "Line 7: 'a' is missing from this listing of /",
+            "Line 9: 'a' is missing from this listing of /",
+            "Line 9: 'b' is missing from this listing of /",
+            "Line 10: cd into 'c' which wasn't listed in /"
+        ]);
+    }
+
+    #[test]
+    fn names_that_leave_their_directory() {
+        assert_eq!(errors("$ ls\ndir ..\n1 .\n2 a/b\ndir /\n"), vec![
+            "Line 2: '..' isn't a valid name",
+            "Line 3: '.' isn't a valid name",
+            "Line 4: 'a/b' isn't a valid name",
+            "Line 5: '/' isn't a valid name"
+        ]);
+    }
+
+    #[test]
+    fn sizes_that_overflow() {
+        let transcript = format!("$ cd /\n$ ls\n{} a\n1 b\n", usize::MAX);
+
+        assert_eq!(errors(&transcript), vec![format!("Line 4: 'b' makes the files add up to more than {} bytes", usize::MAX)]);
+        assert!(interpret(&format!("$ ls\n{} a\n{} a\n", usize::MAX, usize::MAX)).is_ok());
+    }
+}
@@ -1,4 +1,10 @@
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr};
+
+mod image;
+mod sight;
+
+use image::{render, write_images, Layer};
+use sight::{visible_along, visible_from, Observer, Step, COMPASS};
 
 struct Forest {
     trees: Vec<u32>,
@@ -13,156 +19,366 @@ impl Forest {
 }
 
 impl FromStr for Forest {
-    type Err = &'static str;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let t:Vec<_> = s.replace("\r\n", "")
-                                .chars()
-                                .map(|c| c.to_digit(10).unwrap())
-                                .collect(); // Row-major vector
+        let mut forest = Forest::new();
+
+        for (row, line) in s.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+
+            if forest.height == 0 {
+                forest.width = line.chars().count();
+            }
+            else if line.chars().count() != forest.width {
+                return Err(format!("Row {} has {} trees but row 1 has {}", row + 1, line.chars().count(), forest.width));
+            }
+
+            for (col, c) in line.chars().enumerate() {
+                let height = c.to_digit(10)
+                              .ok_or(format!("Row {}, column {}: '{}' is not a tree height", row + 1, col + 1, c))?;
+                forest.trees.push(height); // Row-major vector
+            }
 
-        let rl = s.find("\r\n").unwrap();
-        let cl = t.len() / rl;
+            forest.height += 1;
+        }
+
+        if forest.trees.is_empty() {
+            return Err("Forest has no trees".to_string());
+        }
 
-        Ok(Self {
-            trees: t,
-            width: rl,
-            height: cl
-        })
+        Ok(forest)
     }
 }
 
-enum Direction {
-    North,
-    East,
-    South,
-    West
+// Visibility and scenic score of every tree, found with one pass along
+// each row and column in both directions
+struct Views {
+    visible: Vec<bool>,
+    scores: Vec<usize>
 }
 
-impl Direction {
-    fn get_all() -> Vec<Self> {
-        vec![Direction::North, Direction::East, Direction::South, Direction:: West]
+impl Views {
+    fn new(forest: &Forest) -> Self {
+        let mut views = Self {
+            visible: vec![false; forest.trees.len()],
+            scores: vec![1; forest.trees.len()]
+        };
+
+        for r in 0..forest.height {
+            let row: Vec<_> = (r * forest.width..(r + 1) * forest.width).collect();
+            views.sweep(forest, row.iter().copied());
+            views.sweep(forest, row.iter().rev().copied());
+        }
+
+        for c in 0..forest.width {
+            let col: Vec<_> = (c..forest.trees.len()).step_by(forest.width).collect();
+            views.sweep(forest, col.iter().copied());
+            views.sweep(forest, col.iter().rev().copied());
+        }
+
+        views
+    }
+
+    // Index of the tree with the highest scenic score, the first if tied
+    fn best(&self) -> Option<usize> {
+        self.scores.iter().enumerate().rev().max_by_key(|(_, s)| **s).map(|(i, _)| i)
+    }
+
+    // Walks a line of trees looking back towards where it started. The
+    // stack holds the trees that could still block the view of a later
+    // one, tallest at the bottom, so each tree is pushed and popped once.
+    fn sweep(&mut self, forest: &Forest, line: impl Iterator<Item = usize>) {
+        let mut stack: Vec<(u32, usize)> = Vec::new();
+
+        for (pos, i) in line.enumerate() {
+            let height = forest.trees[i];
+
+            while stack.last().is_some_and(|(h, _)| *h < height) {
+                stack.pop();
+            }
+
+            match stack.last() {
+                Some((_, blocker)) => self.scores[i] *= pos - blocker,
+                None => {
+                    self.scores[i] *= pos;
+                    self.visible[i] = true;
+                }
+            }
+
+            stack.push((height, pos));
+        }
     }
 }
 
-pub fn day8() {
-    println!("Day 8");
+// `--show heights|visible|scores` prints the forest in colour and
+// `--images <dir>` writes it out as PGM/PPM images, with each tree
+// `--scale <n>` pixels across. Both highlight the best scoring tree.
+//
+// `--from <row>,<col>,<eye>` lists the trees an observer there can see
+// looking in the 8 compass directions, and how many they can see in any
+// direction. Rows and columns count from 1, so 0 or one past the edge is
+// outside the forest. `--direction <drow>,<dcol>` looks along that slope
+// instead of the compass directions.
+pub fn day8(args: &[String]) {
     let input = include_str!("input/day8.txt");
 
-    if let Ok(forest) = Forest::from_str(input) {
-        let mut sum = 0;
-    
-        for i in 0..forest.trees.len() {
-            if is_visible(i, &forest) {
-                sum += 1;
+    let mut show = None;
+    let mut images = None;
+    let mut scale = 1;
+    let mut observer = None;
+    let mut direction = None;
+    let mut args = args.iter();
+
+    println!("Day 8");
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--show" => {
+                match args.next().ok_or("--show needs a layer").and_then(|l| Layer::from_str(l)) {
+                    Ok(layer) => show = Some(layer),
+                    Err(e) => println!("Error: {}", e)
+                }
+            }
+            "--images" => {
+                match args.next() {
+                    Some(dir) => images = Some(PathBuf::from(dir)),
+                    None => println!("Error: --images needs a directory")
+                }
+            }
+            "--scale" => {
+                match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => scale = n,
+                    _ => println!("Error: --scale needs a number")
+                }
+            }
+            "--from" => {
+                match args.next().ok_or("--from needs row,col,eye").and_then(|o| Observer::from_str(o)) {
+                    Ok(o) => observer = Some(o),
+                    Err(e) => println!("Error: {}", e)
+                }
             }
+            "--direction" => {
+                match args.next().ok_or("--direction needs drow,dcol").and_then(|d| Step::from_str(d)) {
+                    Ok(step) => direction = Some(step),
+                    Err(e) => println!("Error: {}", e)
+                }
+            }
+            _ => println!("Error: Unknown argument '{}'", arg)
         }
-    
-        println!("Part 1: Trees visible from outside = {}", sum);
-    
-        let mut best = 0;
-    
-        for i in 0..forest.trees.len() {
-            let score = get_scenic_score(i, &forest);
-            if score > best {
-                best = score;
+    }
+
+    let forest = match Forest::from_str(input) {
+        Ok(forest) => forest,
+        Err(e) => {
+            println!("Error: {}", e);
+            return;
+        }
+    };
+
+    let views = Views::new(&forest);
+
+    let sum = views.visible.iter().filter(|v| **v).count();
+    println!("Part 1: Trees visible from outside = {}", sum);
+
+    let best = views.scores.iter().max().unwrap_or(&0);
+    println!("Part 2: Best scenic score = {}", best);
+
+    if let Some(layer) = show {
+        print!("{}", render(&forest, &views, layer));
+    }
+
+    if let Some(dir) = images {
+        match write_images(&forest, &views, &dir, scale) {
+            Ok(paths) => {
+                for path in paths {
+                    println!("Wrote {}", path.display());
+                }
             }
+            Err(e) => println!("Error: {}", e)
         }
-    
-        println!("Part 1: Best scenic score = {}", best);
     }
-    else {
-        println!("Error: Failed to parse input");
+
+    match (&observer, direction) {
+        (Some(observer), Some(step)) => {
+            let trees = visible_along(&forest, observer, step);
+            println!("Sight: Looking along {},{} sees {} trees: {}", step.row, step.col, trees.len(), format_trees(&forest, &trees));
+        }
+        (Some(observer), None) => {
+            for (name, step) in COMPASS {
+                let trees = visible_along(&forest, observer, step);
+                println!("Sight: Looking {} sees {} trees: {}", name, trees.len(), format_trees(&forest, &trees));
+            }
+
+            match visible_from(&forest, observer) {
+                Ok(trees) => println!("Sight: {} trees visible in any direction", trees.len()),
+                Err(e) => println!("Error: {}", e)
+            }
+        }
+        (None, Some(_)) => println!("Error: --direction needs an observer from --from"),
+        (None, None) => {}
     }
 }
 
-fn get_scenic_score(i: usize, forest: &Forest) -> usize {
-    Direction::get_all().into_iter().fold(1, |acc, d| acc * trees_visible_in_direction(d, i, forest))
-}
+// Trees as (row,col) counting from 1
+fn format_trees(forest: &Forest, trees: &[usize]) -> String {
+    if trees.is_empty() {
+        return "none".to_string();
+    }
 
-fn is_visible(i: usize, forest: &Forest) -> bool{
-    Direction::get_all().into_iter().fold(false, |acc, d| acc || is_visible_in_direction(d, i, forest))
+    trees.iter()
+         .map(|i| format!("({},{})", i / forest.width + 1, i % forest.width + 1))
+         .collect::<Vec<_>>()
+         .join(" ")
 }
 
-fn trees_visible_in_direction(dir: Direction, i: usize, forest: &Forest) -> usize {
-    let val = forest.trees.get(i).unwrap();
-    if let Some(n) = get_to_border(dir, i, forest) {
-        let smaller_trees = n.iter().take_while(|&t| *t < val).count();
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
 
-        if n.len() > smaller_trees {
-            return smaller_trees + 1;
+    enum Direction {
+        North,
+        East,
+        South,
+        West
+    }
+
+    impl Direction {
+        fn get_all() -> Vec<Self> {
+            vec![Direction::North, Direction::East, Direction::South, Direction:: West]
         }
-        else {
-            return smaller_trees;
+    }
+
+    fn check_against_original(forest: &Forest) {
+        let views = Views::new(forest);
+
+        for i in 0..forest.trees.len() {
+            assert_eq!(views.visible[i], is_visible(i, forest), "Visibility of tree {} differs", i);
+            assert_eq!(views.scores[i], get_scenic_score(i, forest), "Scenic score of tree {} differs", i);
         }
     }
-    else {
-        // no trees in that direction
-        return 0;
+
+    pub(super) fn random_forest(width: usize, height: usize, seed: u64) -> Forest {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+
+        let trees = (0..width * height).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 10) as u32
+        }).collect();
+
+        Forest { trees, width, height }
+    }
+
+    // Original per-tree versions, kept to check the sweep against
+    fn get_scenic_score(i: usize, forest: &Forest) -> usize {
+        Direction::get_all().into_iter().fold(1, |acc, d| acc * trees_visible_in_direction(d, i, forest))
     }
-}
 
-fn is_visible_in_direction(dir: Direction, i: usize, forest: &Forest) -> bool {
-    let val = forest.trees.get(i).unwrap();
-    if let Some(n) = get_to_border(dir, i, forest) {
-        if n.into_iter().max().unwrap() < val {
-            return true;
+    fn is_visible(i: usize, forest: &Forest) -> bool{
+        Direction::get_all().into_iter().fold(false, |acc, d| acc || is_visible_in_direction(d, i, forest))
+    }
+
+    fn trees_visible_in_direction(dir: Direction, i: usize, forest: &Forest) -> usize {
+        let val = forest.trees.get(i).unwrap();
+        if let Some(n) = get_to_border(dir, i, forest) {
+            let smaller_trees = n.iter().take_while(|&t| *t < val).count();
+
+            if n.len() > smaller_trees {
+                return smaller_trees + 1;
+            }
+            else {
+                return smaller_trees;
+            }
         }
         else {
-            return false;
+            // no trees in that direction
+            return 0;
         }
     }
-    else {
-        return true;
+
+    fn is_visible_in_direction(dir: Direction, i: usize, forest: &Forest) -> bool {
+        let val = forest.trees.get(i).unwrap();
+        if let Some(n) = get_to_border(dir, i, forest) {
+            if n.into_iter().max().unwrap() < val {
+                return true;
+            }
+            else {
+                return false;
+            }
+        }
+        else {
+            return true;
+        }
     }
-}
 
 
-fn get_to_border(dir: Direction, i: usize, forest: &Forest) -> Option::<Vec<&u32>> {
-    match dir {
-        Direction::North => {
-            let border_dist = i / forest.height;
-            let row_i = i % forest.width;
-            if border_dist == 0 {
-                return None;
-            }
-            return Some(forest.trees.iter().skip(row_i)
-                                    .step_by(forest.width)
-                                    .take(border_dist)
-                                    .rev()
-                                    .collect());
-
-        }
-        Direction::East => {
-            let border_dist = forest.width - (i % forest.width) - 1;
-            if border_dist == 0 {
-                return None;
-            }
-            return Some(forest.trees.iter().skip(i+1)
-                                    .take(border_dist)
-                                    .collect());
-        }
-        Direction::South => {
-            let col_i = i / forest.width;
-            let border_dist = forest.height - col_i - 1;
-            if border_dist == 0 {
-                return None;
-            }
-            return Some(forest.trees.iter().skip(i + forest.width)
-                                    .step_by(forest.width)
-                                    .take(border_dist)
-                                    .collect());
-        },
-        Direction::West => {
-            let border_dist = i % forest.width;
-            let col_i = i / forest.width;
-            if border_dist == 0 {
-                return None;
-            }
-            return Some(forest.trees.iter().skip(col_i * forest.width)
-                                    .take(border_dist)
-                                    .rev()
-                                    .collect());
+    fn get_to_border(dir: Direction, i: usize, forest: &Forest) -> Option::<Vec<&u32>> {
+        match dir {
+            Direction::North => {
+                let border_dist = i / forest.width;
+                let col_i = i % forest.width;
+                if border_dist == 0 {
+                    return None;
+                }
+                return Some(forest.trees.iter().skip(col_i)
+                                        .step_by(forest.width)
+                                        .take(border_dist)
+                                        .rev()
+                                        .collect());
+
+            }
+            Direction::East => {
+                let border_dist = forest.width - (i % forest.width) - 1;
+                if border_dist == 0 {
+                    return None;
+                }
+                return Some(forest.trees.iter().skip(i+1)
+                                        .take(border_dist)
+                                        .collect());
+            }
+            Direction::South => {
+                let row_i = i / forest.width;
+                let border_dist = forest.height - row_i - 1;
+                if border_dist == 0 {
+                    return None;
+                }
+                return Some(forest.trees.iter().skip(i + forest.width)
+                                        .step_by(forest.width)
+                                        .take(border_dist)
+                                        .collect());
+            },
+            Direction::West => {
+                let border_dist = i % forest.width;
+                let row_i = i / forest.width;
+                if border_dist == 0 {
+                    return None;
+                }
+                return Some(forest.trees.iter().skip(row_i * forest.width)
+                                        .take(border_dist)
+                                        .rev()
+                                        .collect());
+            }
+        };
+    }
+
+    #[test]
+    fn sweep_matches_original_on_input() {
+        let forest = Forest::from_str(include_str!("input/day8.txt")).unwrap();
+        check_against_original(&forest);
+    }
+
+    #[test]
+    fn sweep_matches_original_on_random_forests() {
+        for width in 1..=12 {
+            for height in 1..=12 {
+                check_against_original(&random_forest(width, height, (width * 100 + height) as u64));
+            }
         }
-    };
-}
\ No newline at end of file
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr
+};
+
+use super::{Forest, Views};
+
+type Rgb = [u8; 3];
+
+// Colour used to pick out the best scoring tree
+const BEST: Rgb = [0, 255, 255];
+
+#[derive(Clone, Copy)]
+pub enum Layer {
+    Heights,
+    Visible,
+    Scores
+}
+
+impl FromStr for Layer {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "heights" => Ok(Layer::Heights),
+            "visible" => Ok(Layer::Visible),
+            "scores" => Ok(Layer::Scores),
+            _ => Err("Layer must be heights, visible or scores")
+        }
+    }
+}
+
+fn height_shade(height: u32) -> u8 {
+    (height.min(9) * 255 / 9) as u8
+}
+
+// Scores span several orders of magnitude, so shade on a log scale to keep
+// the low scoring trees apart
+fn score_fraction(score: usize, max: usize) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+
+    (score as f64).ln_1p() / (max as f64).ln_1p()
+}
+
+// Black through red and yellow to white
+fn heat(t: f64) -> Rgb {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+    let channel = |from: f64| ((t - from).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    [channel(0.0), channel(1.0), channel(2.0)]
+}
+
+// Colour of tree `i`, with scores shaded relative to `max_score`
+fn colour(layer: Layer, forest: &Forest, views: &Views, max_score: usize, i: usize) -> Rgb {
+    match layer {
+        Layer::Heights => [0, height_shade(forest.trees[i]), 0],
+        Layer::Visible if views.visible[i] => [40, 200, 40],
+        Layer::Visible => [60, 60, 60],
+        Layer::Scores => heat(score_fraction(views.scores[i], max_score))
+    }
+}
+
+// Each tree becomes a `scale` x `scale` block of pixels
+fn upscale<T: Copy>(pixels: &[T], width: usize, scale: usize) -> Vec<T> {
+    pixels.chunks(width).flat_map(|row| {
+        let wide: Vec<_> = row.iter().flat_map(|p| std::iter::repeat_n(*p, scale)).collect();
+        std::iter::repeat_n(wide, scale).flatten()
+    }).collect()
+}
+
+// Binary greyscale image, one byte per pixel
+fn write_pgm(path: &Path, width: usize, height: usize, pixels: &[u8]) -> io::Result<()> {
+    let mut data = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    data.extend_from_slice(pixels);
+    fs::write(path, data)
+}
+
+// Binary colour image, three bytes per pixel
+fn write_ppm(path: &Path, width: usize, height: usize, pixels: &[Rgb]) -> io::Result<()> {
+    let mut data = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    data.extend(pixels.iter().flatten());
+    fs::write(path, data)
+}
+
+// Writes the tree heights and visible mask as greyscale images and the
+// scenic scores as a heatmap with the best tree highlighted, returning the
+// files written
+pub fn write_images(forest: &Forest, views: &Views, dir: &Path, scale: usize) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+
+    let (width, height) = (forest.width * scale, forest.height * scale);
+    let paths = [dir.join("heights.pgm"), dir.join("visible.pgm"), dir.join("scores.ppm")];
+
+    let heights: Vec<_> = forest.trees.iter().map(|h| height_shade(*h)).collect();
+    write_pgm(&paths[0], width, height, &upscale(&heights, forest.width, scale))?;
+
+    let visible: Vec<_> = views.visible.iter().map(|v| if *v { 255 } else { 0 }).collect();
+    write_pgm(&paths[1], width, height, &upscale(&visible, forest.width, scale))?;
+
+    let best = views.best();
+    let max_score = best.map_or(0, |b| views.scores[b]);
+
+    let mut scores: Vec<_> = (0..forest.trees.len()).map(|i| colour(Layer::Scores, forest, views, max_score, i)).collect();
+    if let Some(best) = best {
+        scores[best] = BEST;
+    }
+    write_ppm(&paths[2], width, height, &upscale(&scores, forest.width, scale))?;
+
+    Ok(paths.to_vec())
+}
+
+// The forest as coloured digits for a terminal with 24-bit colour, with the
+// best scoring tree highlighted
+pub fn render(forest: &Forest, views: &Views, layer: Layer) -> String {
+    let best = views.best();
+    let max_score = best.map_or(0, |b| views.scores[b]);
+    let mut out = String::new();
+
+    for (r, row) in forest.trees.chunks(forest.width).enumerate() {
+        for (c, tree) in row.iter().enumerate() {
+            let i = r * forest.width + c;
+            let [red, green, blue] = if best == Some(i) { BEST } else { colour(layer, forest, views, max_score, i) };
+
+            // Dark text on light backgrounds so the digit stays readable
+            let text = if red as u32 * 3 + green as u32 * 6 + blue as u32 > 1280 { 0 } else { 255 };
+
+            out.push_str(&format!("\x1b[48;2;{};{};{}m\x1b[38;2;{t};{t};{t}m{}", red, green, blue, tree, t = text));
+        }
+
+        out.push_str("\x1b[0m\n");
+    }
+
+    if let Some(best) = best {
+        out.push_str(&format!("Best tree: row {}, column {}, scenic score {}\n",
+                              best / forest.width + 1,
+                              best % forest.width + 1,
+                              views.scores[best]));
+    }
+
+    out
+}
@@ -0,0 +1,300 @@
+use std::{collections::HashMap, str::FromStr};
+
+use super::Forest;
+
+// Someone looking over the forest. Rows and columns count from 0 here and
+// can be outside the forest, the eye height is in the same units as trees.
+pub struct Observer {
+    pub row: i64,
+    pub col: i64,
+    pub eye: f64
+}
+
+impl FromStr for Observer {
+    type Err = &'static str;
+
+    // `row,col,eye` with rows and columns counting from 1 like the rest of
+    // the output, so 0 is just outside the top or left edge
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(',').collect();
+
+        if parts.len() != 3 {
+            return Err("Observer must be of the form row,col,eye");
+        }
+
+        let row = parts[0].parse::<i64>().map_err(|_| "Failed to parse observer row")?;
+        let col = parts[1].parse::<i64>().map_err(|_| "Failed to parse observer column")?;
+        let eye = parts[2].parse::<f64>().map_err(|_| "Failed to parse observer eye height")?;
+
+        if !eye.is_finite() {
+            return Err("Observer eye height must be a number");
+        }
+
+        let row = row.checked_sub(1).ok_or("Observer row is too far from the forest")?;
+        let col = col.checked_sub(1).ok_or("Observer column is too far from the forest")?;
+
+        Ok(Observer { row, col, eye })
+    }
+}
+
+// Smallest whole step along a line of sight, so every tree it passes
+// through exactly is one of its multiples
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Step {
+    pub row: i64,
+    pub col: i64
+}
+
+impl Step {
+    pub fn new(row: i64, col: i64) -> Option<Self> {
+        if row == 0 && col == 0 {
+            return None;
+        }
+
+        // Dividing by the gcd never grows either part, so this fits back
+        // into an i64 even when the gcd doesn't
+        let g = gcd(row, col) as i128;
+        Some(Self { row: (row as i128 / g) as i64, col: (col as i128 / g) as i64 })
+    }
+}
+
+impl FromStr for Step {
+    type Err = &'static str;
+
+    // `drow,dcol`, any slope as a ratio of whole numbers
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (row, col) = s.split_once(',').ok_or("Direction must be of the form drow,dcol")?;
+
+        let row = row.parse::<i64>().map_err(|_| "Failed to parse direction rows")?;
+        let col = col.parse::<i64>().map_err(|_| "Failed to parse direction columns")?;
+
+        Step::new(row, col).ok_or("Direction can't be 0,0")
+    }
+}
+
+pub const COMPASS: [(&str, Step); 8] = [
+    ("N", Step { row: -1, col: 0 }),
+    ("NE", Step { row: -1, col: 1 }),
+    ("E", Step { row: 0, col: 1 }),
+    ("SE", Step { row: 1, col: 1 }),
+    ("S", Step { row: 1, col: 0 }),
+    ("SW", Step { row: 1, col: -1 }),
+    ("W", Step { row: 0, col: -1 }),
+    ("NW", Step { row: -1, col: -1 })
+];
+
+fn gcd(a: i64, b: i64) -> u64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+fn tree_at(forest: &Forest, row: i128, col: i128) -> Option<usize> {
+    if row < 0 || col < 0 || row >= forest.height as i128 || col >= forest.width as i128 {
+        return None;
+    }
+
+    Some(row as usize * forest.width + col as usize)
+}
+
+fn floor_div(a: i128, b: i128) -> i128 {
+    let q = a / b;
+
+    if a % b != 0 && (a < 0) != (b < 0) { q - 1 } else { q }
+}
+
+fn ceil_div(a: i128, b: i128) -> i128 {
+    -floor_div(-a, b)
+}
+
+// Range of multiples of `step` from `start` that land in 0..len
+fn steps_inside(start: i64, step: i64, len: usize) -> Option<(i128, i128)> {
+    let (start, step, last) = (start as i128, step as i128, len as i128 - 1);
+
+    match step {
+        0 if (0..=last).contains(&start) => Some((i128::MIN, i128::MAX)),
+        0 => None,
+        _ if step > 0 => Some((ceil_div(-start, step), floor_div(last - start, step))),
+        _ => Some((ceil_div(last - start, step), floor_div(-start, step)))
+    }
+}
+
+// Steepest sight line so far along one ray. Trees are only compared along
+// the same ray, so the slope can be rise over steps rather than distance.
+struct Horizon {
+    eye: f64,
+    steepest: Option<f64>
+}
+
+impl Horizon {
+    fn new(eye: f64) -> Self {
+        Self { eye, steepest: None }
+    }
+
+    // Adds the tree `steps` along, returning whether its top shows above
+    // everything nearer. A tree exactly on the sight line is hidden.
+    fn sees(&mut self, height: u32, steps: i128) -> bool {
+        let slope = (height as f64 - self.eye) / steps as f64;
+        let visible = self.steepest.is_none_or(|s| slope > s);
+
+        if visible {
+            self.steepest = Some(slope);
+        }
+
+        visible
+    }
+}
+
+// Trees the observer can see looking along one direction, nearest first.
+// Only the steps that land in the forest are walked, however far away the
+// observer is.
+pub fn visible_along(forest: &Forest, observer: &Observer, step: Step) -> Vec<usize> {
+    let mut horizon = Horizon::new(observer.eye);
+    let mut out = Vec::new();
+
+    let rows = steps_inside(observer.row, step.row, forest.height);
+    let cols = steps_inside(observer.col, step.col, forest.width);
+
+    let Some(((row_lo, row_hi), (col_lo, col_hi))) = rows.zip(cols) else {
+        return out;
+    };
+
+    for k in row_lo.max(col_lo).max(1)..=row_hi.min(col_hi) {
+        let row = observer.row as i128 + k * step.row as i128;
+        let col = observer.col as i128 + k * step.col as i128;
+
+        if let Some(i) = tree_at(forest, row, col) {
+            if horizon.sees(forest.trees[i], k) {
+                out.push(i);
+            }
+        }
+    }
+
+    out
+}
+
+// Every tree the observer can see in any direction. Trees are treated as
+// thin posts, so only ones exactly on the line to a tree can block it. Each
+// tree lies on exactly one ray from the observer, so group them by ray and
+// sweep each outwards.
+pub fn visible_from(forest: &Forest, observer: &Observer) -> Result<Vec<usize>, &'static str> {
+    let mut rays: HashMap<Step, Vec<(i128, usize)>> = HashMap::new();
+
+    for i in 0..forest.trees.len() {
+        let row = ((i / forest.width) as i64).checked_sub(observer.row);
+        let col = ((i % forest.width) as i64).checked_sub(observer.col);
+
+        let (Some(row), Some(col)) = (row, col) else {
+            return Err("Observer is too far from the forest");
+        };
+
+        if let Some(step) = Step::new(row, col) {
+            rays.entry(step).or_default().push((gcd(row, col) as i128, i));
+        }
+    }
+
+    let mut out = Vec::new();
+
+    for trees in rays.values_mut() {
+        trees.sort();
+
+        let mut horizon = Horizon::new(observer.eye);
+        out.extend(trees.iter().filter(|(k, i)| horizon.sees(forest.trees[*i], *k)).map(|(_, i)| *i));
+    }
+
+    out.sort();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use super::super::tests::random_forest;
+
+    // Checks `visible_from` against testing every tree on its own, by looking
+    // for anything on the straight line to it that blocks it
+    fn check_sight(forest: &Forest, observer: &Observer) {
+        let seen = visible_from(forest, observer).unwrap();
+
+        for i in 0..forest.trees.len() {
+            let row = (i / forest.width) as i64 - observer.row;
+            let col = (i % forest.width) as i64 - observer.col;
+
+            let Some(step) = Step::new(row, col) else {
+                assert!(seen.binary_search(&i).is_err(), "Observer sees the tree they're standing on");
+                continue;
+            };
+
+            let k = gcd(row, col) as i64;
+            let slope = (forest.trees[i] as f64 - observer.eye) / k as f64;
+
+            let blocked = (1..k).any(|j| {
+                tree_at(forest, (observer.row + j * step.row).into(), (observer.col + j * step.col).into())
+                    .is_some_and(|b| (forest.trees[b] as f64 - observer.eye) / j as f64 >= slope)
+            });
+
+            assert_eq!(seen.binary_search(&i).is_ok(), !blocked, "Visibility of tree {} from the observer differs", i);
+        }
+
+        for (name, step) in COMPASS {
+            for i in visible_along(forest, observer, step) {
+                assert!(seen.binary_search(&i).is_ok(), "Tree {} seen looking {} but not from the observer", i, name);
+            }
+        }
+    }
+
+    #[test]
+    fn visible_from_matches_each_tree_on_its_own() {
+        for (i, (width, height)) in [(1, 1), (3, 7), (9, 4), (12, 12), (20, 15)].into_iter().enumerate() {
+            let forest = random_forest(width, height, i as u64 + 1);
+
+            for row in -2..height as i64 + 2 {
+                for col in -2..width as i64 + 2 {
+                    for eye in [0.0, 2.5, 5.0, 9.5] {
+                        check_sight(&forest, &Observer { row, col, eye });
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn looking_into_the_forest_from_the_edge() {
+        let forest = Forest::from_str("30373\n25512\n65332\n33549\n35390").unwrap();
+        let south = Step::new(1, 0).unwrap();
+
+        // From the ground only the first tree shows, the rest are lower
+        // than it as seen from here
+        let low = Observer { row: -1, col: 2, eye: 0.0 };
+        assert_eq!(visible_along(&forest, &low, south), vec![2]);
+
+        // From above the trees their tops show until one is hidden
+        let high = Observer { row: -1, col: 2, eye: 9.5 };
+        assert_eq!(visible_along(&forest, &high, south), vec![2, 7, 12, 17]);
+    }
+
+    #[test]
+    fn far_away_observers_dont_overflow() {
+        let forest = Forest::from_str("30373\n25512\n65332\n33549\n35390").unwrap();
+        let south = Step::new(1, 0).unwrap();
+
+        let far = Observer { row: -2_000_000_000, col: 2, eye: 0.0 };
+        assert_eq!(visible_along(&forest, &far, south), vec![2, 7]);
+
+        let edge = Observer { row: i64::MIN, col: i64::MAX, eye: 0.0 };
+        assert!(visible_from(&forest, &edge).is_err());
+
+        assert!(Step::new(i64::MIN, 0) == Step::new(-1, 0));
+
+        for step in [Step::new(i64::MAX, i64::MIN), Step::new(1, i64::MAX)].into_iter().flatten() {
+            assert!(visible_along(&forest, &edge, step).is_empty());
+            assert!(visible_along(&forest, &Observer { row: 2, col: 2, eye: 0.0 }, step).is_empty());
+        }
+    }
+}
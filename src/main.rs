@@ -1,18 +1,31 @@
 #![allow(dead_code)]
+use std::env;
+
 mod days;
 
+// Usage: cargo run -- <day> [options]
 fn main() {
-    // days::day1();
-    // days::day2();
-    // days::day3();
-    // days::day4();
-    // days::day5();
-    // days::day6();
-    // days::day7();
-    // days::day8();
-    // days::day9();
-    // days::day10();
-    // days::day11();
-    // days::day12();
-    days::day13();
-}
\ No newline at end of file
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = args.get(1..).unwrap_or(&[]);
+
+    match args.first().map(String::as_str) {
+        Some("1") => days::day1(),
+        Some("2") => days::day2(options),
+        Some("3") => days::day3(options),
+        Some("4") => days::day4(),
+        Some("5") => days::day5(options),
+        Some("6") => days::day6(options),
+        Some("7") => days::day7(options),
+        Some("8") => days::day8(options),
+        Some("9") => days::day9(),
+        Some("10") => days::day10(),
+        Some("11") => days::day11(),
+        Some("12") => days::day12(),
+        Some("13") => days::day13(),
+        Some(day) => {
+            println!("Error: Unknown day '{}', expected 1 to 13", day);
+            println!("Usage: cargo run -- <day> [options]");
+        }
+        None => println!("Usage: cargo run -- <day> [options]")
+    }
+}